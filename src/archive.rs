@@ -0,0 +1,186 @@
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use serde_with::DeserializeFromStr;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use strum::EnumVariantNames;
+use tar::{Archive, EntryType};
+use thiserror::Error;
+use xz2::read::XzDecoder;
+
+/// Compression of a source tarball
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumVariantNames, DeserializeFromStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum Format {
+    Xz,
+    Gz,
+    Bz2,
+}
+
+/// Smaller archives first
+pub const DEFAULT_PREFERENCE: [Format; 3] = [Format::Xz, Format::Gz, Format::Bz2];
+
+#[derive(Error, Debug)]
+pub enum ParseFormatError {
+    #[error("Unknown archive format: {0}")]
+    UnknownFormat(String),
+}
+
+impl FromStr for Format {
+    type Err = ParseFormatError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xz" => Ok(Format::Xz),
+            "gz" => Ok(Format::Gz),
+            "bz2" => Ok(Format::Bz2),
+            _ => Err(ParseFormatError::UnknownFormat(s.to_owned())),
+        }
+    }
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Xz => ".tar.xz",
+            Format::Gz => ".tar.gz",
+            Format::Bz2 => ".tar.bz2",
+        }
+    }
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        DEFAULT_PREFERENCE
+            .into_iter()
+            .find(|format| filename.ends_with(format.extension()))
+    }
+    fn decoder<'a>(&self, reader: impl Read + 'a) -> Box<dyn Read + 'a> {
+        match self {
+            Format::Xz => Box::new(XzDecoder::new(reader)),
+            Format::Gz => Box::new(GzDecoder::new(reader)),
+            Format::Bz2 => Box::new(BzDecoder::new(reader)),
+        }
+    }
+}
+
+/// Unpacks the tarball into `dst_dir`, and returns the path of its top-level entry
+pub fn unpack(reader: impl Read, format: Format, dst_dir: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let mut archive = Archive::new(format.decoder(reader));
+    let mut top_level = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        // metadata of the following entries, e.g. `pax_global_header` of `git archive`
+        if matches!(
+            entry.header().entry_type(),
+            EntryType::XGlobalHeader | EntryType::XHeader
+        ) {
+            continue;
+        }
+        if top_level.is_none() {
+            top_level = entry
+                .path()?
+                .components()
+                .find_map(|component| match component {
+                    Component::Normal(name) => Some(name.to_owned()),
+                    _ => None,
+                });
+        }
+        entry.unpack_in(&dst_dir)?;
+    }
+    top_level
+        .map(|name| dst_dir.as_ref().join(name))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "The archive is empty"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn tarball(format: Format) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "./php-src-php-8.2.10/NEWS", &b"news"[..])
+            .unwrap();
+        let tar = builder.into_inner().unwrap();
+        match format {
+            Format::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(&tar).unwrap();
+                encoder.finish().unwrap()
+            }
+            Format::Gz => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&tar).unwrap();
+                encoder.finish().unwrap()
+            }
+            Format::Bz2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(&tar).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn unpack_each_format() {
+        for format in DEFAULT_PREFERENCE {
+            let dst_dir = tempfile::tempdir().unwrap();
+            let source_dir = unpack(&tarball(format)[..], format, &dst_dir).unwrap();
+            // not derived from the archive's filename
+            assert_eq!(source_dir, dst_dir.path().join("php-src-php-8.2.10"));
+            assert_eq!(std::fs::read(source_dir.join("NEWS")).unwrap(), b"news");
+        }
+    }
+
+    #[test]
+    fn unpack_git_archive() {
+        if which::which("git").is_err() {
+            return;
+        }
+        let repository = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=phpup",
+                    "-c",
+                    "user.email=phpup@example.com",
+                ])
+                .args(args)
+                .current_dir(&repository)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+            output.stdout
+        };
+        git(&["init", "--quiet"]);
+        std::fs::write(repository.path().join("NEWS"), "news").unwrap();
+        git(&["add", "NEWS"]);
+        git(&["commit", "--quiet", "-m", "NEWS"]);
+        let tarball = git(&[
+            "archive",
+            "--format=tar.gz",
+            "--prefix=php-src-php-8.2.10/",
+            "HEAD",
+        ]);
+
+        let dst_dir = tempfile::tempdir().unwrap();
+        let source_dir = unpack(&tarball[..], Format::Gz, &dst_dir).unwrap();
+        assert_eq!(source_dir, dst_dir.path().join("php-src-php-8.2.10"));
+        assert_eq!(std::fs::read(source_dir.join("NEWS")).unwrap(), b"news");
+    }
+
+    #[test]
+    fn detect_format() {
+        assert_eq!(Format::from_filename("php-8.2.10.tar.xz"), Some(Format::Xz));
+        assert_eq!(
+            Format::from_filename("php-8.2.10.tar.bz2"),
+            Some(Format::Bz2)
+        );
+        assert_eq!(Format::from_filename("php-8.2.10.zip"), None);
+    }
+}
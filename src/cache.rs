@@ -0,0 +1,214 @@
+use crate::release::Hash;
+use chrono::{DateTime, Duration, Local};
+use serde_with::DeserializeFromStr;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// Content-addressed store of downloaded source archives: `<dir>/<checksum>/<filename>`
+pub struct DownloadCache {
+    dir: PathBuf,
+}
+
+pub struct Entry {
+    pub path: PathBuf,
+    pub filename: String,
+    pub checksum: String,
+    pub size: u64,
+    pub last_used: DateTime<Local>,
+}
+
+impl DownloadCache {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+    /// `None` if the checksum or the filename from the release source can't be a path component
+    fn filepath(&self, filename: &str, checksum: &Hash) -> Option<PathBuf> {
+        let expected_len = match checksum {
+            Hash::SHA256(_) => 64,
+            Hash::MD5(_) => 32,
+        };
+        let key = checksum.value();
+        let is_key = key.len() == expected_len
+            && key.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+        let is_filename = Path::new(filename).file_name() == Some(OsStr::new(filename));
+        (is_key && is_filename).then(|| self.dir.join(key).join(filename))
+    }
+    /// Returns the cached file, or `None` if it's not cached.
+    /// The caller must verify it before use.
+    pub fn get(&self, filename: &str, checksum: &Hash) -> Option<PathBuf> {
+        let filepath = self.filepath(filename, checksum)?;
+        filepath.is_file().then(|| {
+            // keep track of the last use for `prune`
+            if let Ok(file) = fs::File::options().append(true).open(&filepath) {
+                file.set_modified(SystemTime::now()).ok();
+            }
+            filepath
+        })
+    }
+    /// Returns the path to download a file into, which is resumed if interrupted,
+    /// or `None` if it can't be cached
+    pub fn download_path(
+        &self,
+        filename: &str,
+        checksum: &Hash,
+    ) -> std::io::Result<Option<PathBuf>> {
+        let Some(filepath) = self.filepath(filename, checksum) else {
+            return Ok(None);
+        };
+        fs::create_dir_all(filepath.parent().unwrap())?;
+        Ok(Some(filepath))
+    }
+    pub fn remove(&self, entry: &Entry) -> std::io::Result<()> {
+        fs::remove_dir_all(entry.path.parent().unwrap())
+    }
+    pub fn entries(&self) -> impl Iterator<Item = Entry> {
+        fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .flat_map(|checksum_dir| {
+                let checksum = checksum_dir.file_name().to_string_lossy().into_owned();
+                fs::read_dir(checksum_dir.path())
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .flat_map(move |file| {
+                        let metadata = file.metadata().ok()?;
+                        // partial downloads and signatures go with the archive
+                        let is_archive = !matches!(
+                            file.path().extension().and_then(|ext| ext.to_str()),
+                            Some("part" | "asc")
+                        );
+                        (metadata.is_file() && is_archive).then(|| Entry {
+                            path: file.path(),
+                            filename: file.file_name().to_string_lossy().into_owned(),
+                            checksum: checksum.clone(),
+                            size: metadata.len(),
+                            last_used: metadata
+                                .modified()
+                                .map(DateTime::from)
+                                .unwrap_or_else(|_| Local::now()),
+                        })
+                    })
+            })
+    }
+}
+
+/// A period of time written as `<number><unit>`: `30d`, `12h`, `2w`, `90m`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, DeserializeFromStr)]
+pub struct Period(pub Duration);
+
+#[derive(Error, Debug)]
+pub enum ParsePeriodError {
+    #[error("Invalid period: \"{0}\"; Use a number with a unit m, h, d or w (e.g. 30d)")]
+    InvalidFormat(String),
+
+    #[error("Too long period: \"{0}\"")]
+    OutOfRange(String),
+}
+
+impl FromStr for Period {
+    type Err = ParsePeriodError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParsePeriodError::InvalidFormat(s.to_owned());
+        let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?);
+        let number = number.parse::<i64>().map_err(|_| invalid())?;
+        let seconds_per_unit = match unit {
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let seconds = number
+            .checked_mul(seconds_per_unit)
+            .filter(|&seconds| seconds <= Duration::max_value().num_seconds())
+            .ok_or_else(|| ParsePeriodError::OutOfRange(s.to_owned()))?;
+        Ok(Self(Duration::seconds(seconds)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn download_and_get() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(&cache_dir);
+        let checksum = Hash::SHA256("0123abcd".repeat(8));
+        assert!(cache.get("php-8.2.10.tar.gz", &checksum).is_none());
+
+        let cached = cache
+            .download_path("php-8.2.10.tar.gz", &checksum)
+            .unwrap()
+            .unwrap();
+        fs::File::create(cached.with_extension("gz.part"))
+            .unwrap()
+            .write_all(b"da")
+            .unwrap();
+        assert!(cache.get("php-8.2.10.tar.gz", &checksum).is_none());
+        assert_eq!(cache.entries().count(), 0);
+
+        fs::File::create(&cached)
+            .unwrap()
+            .write_all(b"data")
+            .unwrap();
+        assert_eq!(cache.get("php-8.2.10.tar.gz", &checksum), Some(cached));
+        assert!(cache
+            .get("php-8.2.10.tar.gz", &Hash::SHA256("f".repeat(64)))
+            .is_none());
+
+        let entries = cache.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].checksum, "0123abcd".repeat(8));
+        assert_eq!(entries[0].size, 4);
+
+        cache.remove(&entries[0]).unwrap();
+        assert_eq!(cache.entries().count(), 0);
+    }
+
+    #[test]
+    fn refuse_unsafe_paths() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(cache_dir.path().join("cache"));
+        for (filename, checksum) in [
+            (
+                "php-8.2.10.tar.gz",
+                Hash::SHA256("../../escaped".to_owned()),
+            ),
+            ("php-8.2.10.tar.gz", Hash::SHA256("0123ABCD".repeat(8))),
+            ("php-8.2.10.tar.gz", Hash::MD5("0123abcd".repeat(8))),
+            ("..", Hash::SHA256("0123abcd".repeat(8))),
+        ] {
+            assert!(cache.download_path(filename, &checksum).unwrap().is_none());
+            assert!(cache.get(filename, &checksum).is_none());
+        }
+        assert!(!cache_dir.path().join("cache").exists());
+    }
+
+    #[test]
+    fn parse_period() {
+        assert_eq!("30d".parse::<Period>().unwrap().0, Duration::days(30));
+        assert_eq!("12h".parse::<Period>().unwrap().0, Duration::hours(12));
+        assert_eq!("2w".parse::<Period>().unwrap().0, Duration::weeks(2));
+        assert!("30".parse::<Period>().is_err());
+        assert!("d".parse::<Period>().is_err());
+        assert!("30y".parse::<Period>().is_err());
+        assert!(matches!(
+            "99999999999999999w".parse::<Period>(),
+            Err(ParsePeriodError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            "9223372036854775807m".parse::<Period>(),
+            Err(ParsePeriodError::OutOfRange(_))
+        ));
+    }
+}
@@ -1,5 +1,8 @@
 use crate::commands::{self, Command};
 use crate::config::Config;
+use crate::variant::Variant;
+use clap::{CommandFactory, Parser};
+use std::ffi::OsString;
 
 #[derive(clap::Parser, Debug)]
 #[command(
@@ -27,7 +30,15 @@ pub enum SubCommand {
 
     /// Install a new PHP version
     #[command(bin_name = "install")]
-    Install(commands::Install),
+    Install(Box<commands::Install>),
+
+    /// Build an installed PHP version again with its recorded options
+    #[command(bin_name = "reinstall")]
+    Reinstall(commands::Reinstall),
+
+    /// Install the latest patch of installed minor versions with the same options
+    #[command(bin_name = "upgrade")]
+    Upgrade(commands::Upgrade),
 
     /// List local PHP versions
     #[command(bin_name = "list", visible_aliases = &["ls"])]
@@ -41,10 +52,30 @@ pub enum SubCommand {
     #[command(bin_name = "current")]
     Current(commands::Current),
 
+    /// Print how an installed PHP version was built, its `php -v` and modules
+    #[command(bin_name = "info")]
+    Info(commands::Info),
+
+    /// Print the latest build log of a PHP version
+    #[command(bin_name = "logs")]
+    Logs(commands::Logs),
+
     /// Uninstall a PHP version
     #[command(bin_name = "uninstall")]
     Uninstall(commands::Uninstall),
 
+    /// List installed PHP versions that have a newer patch release
+    #[command(bin_name = "outdated")]
+    Outdated(commands::Outdated),
+
+    /// Check installed PHP versions for security releases and end of life
+    #[command(bin_name = "audit")]
+    Audit(commands::Audit),
+
+    /// Check the tools and libraries needed to build a PHP version
+    #[command(bin_name = "doctor")]
+    Doctor(commands::Doctor),
+
     /// Alias a version to a common name
     #[command(bin_name = "alias")]
     Alias(commands::Alias),
@@ -57,6 +88,10 @@ pub enum SubCommand {
     #[command(bin_name = "default")]
     Default(commands::Default),
 
+    /// Manage the download cache of PHP source archives
+    #[command(bin_name = "cache")]
+    Cache(commands::Cache),
+
     /// Print shell completions
     #[command(bin_name = "completions")]
     Completions(commands::Completions),
@@ -69,14 +104,115 @@ impl SubCommand {
             Init(cmd) => cmd.apply(&config),
             ListRemote(cmd) => cmd.apply(&config),
             Install(cmd) => cmd.apply(&config),
+            Reinstall(cmd) => cmd.apply(&config),
+            Upgrade(cmd) => cmd.apply(&config),
             List(cmd) => cmd.apply(&config),
             Use(cmd) => cmd.apply(&config),
             Current(cmd) => cmd.apply(&config),
+            Info(cmd) => cmd.apply(&config),
+            Logs(cmd) => cmd.apply(&config),
             Uninstall(cmd) => cmd.apply(&config),
+            Outdated(cmd) => cmd.apply(&config),
+            Audit(cmd) => cmd.apply(&config),
+            Doctor(cmd) => cmd.apply(&config),
             Alias(cmd) => cmd.apply(&config),
             Unalias(cmd) => cmd.apply(&config),
             Default(cmd) => cmd.apply(&config),
+            Cache(cmd) => cmd.apply(&config),
             Completions(cmd) => cmd.apply(&config),
         };
     }
 }
+
+impl Cli {
+    /// Parses the command line, taking `-VARIANT` of `install` and `doctor` where it's given
+    pub fn parse_args() -> Self {
+        Self::parse_from(escape_variants(&Self::command(), std::env::args_os()))
+    }
+    pub fn try_parse_args<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        Self::try_parse_from(escape_variants(&Self::command(), args))
+    }
+}
+
+/// Moves the versions and variants of a command taking `-VARIANT` after `--`, in their order.
+/// Otherwise `-cgi` would be taken for short options, and a positional accepting hyphens
+/// would take the options following it as its values.
+pub fn escape_variants<I, T>(cmd: &clap::Command, args: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let takes_variants = |cmd: &clap::Command| {
+        cmd.get_positionals().any(|arg| {
+            arg.get_value_names()
+                .is_some_and(|names| names.iter().any(|name| name.ends_with("-VARIANT")))
+        })
+    };
+    let mut cmd = cmd.clone();
+    cmd.build();
+    let mut cmd = &cmd;
+
+    let mut args = args.into_iter().map(Into::into);
+    let mut escaped: Vec<OsString> = args.next().into_iter().collect();
+    let mut targets = takes_variants(cmd).then(Vec::new);
+    while let Some(arg) = args.next() {
+        let Some(s) = arg.to_str() else {
+            targets.as_mut().unwrap_or(&mut escaped).push(arg);
+            continue;
+        };
+        if s == "--" {
+            match targets.as_mut() {
+                Some(targets) => targets.extend(args.by_ref()),
+                None => escaped.extend([arg].into_iter().chain(args.by_ref())),
+            }
+            break;
+        }
+        if let Some(long) = s.strip_prefix("--") {
+            let takes_value = !long.contains('=')
+                && cmd
+                    .get_arguments()
+                    .any(|opt| opt.get_long() == Some(long) && opt.get_action().takes_values());
+            escaped.push(arg);
+            if takes_value {
+                escaped.extend(args.next());
+            }
+            continue;
+        }
+        if s.len() > 2 && s.starts_with('-') && s.parse::<Variant>().is_ok() {
+            if let Some(targets) = targets.as_mut() {
+                targets.push(arg);
+                continue;
+            }
+        }
+        if let Some(shorts) = s.strip_prefix('-').filter(|shorts| !shorts.is_empty()) {
+            let takes_value = shorts.chars().count() == 1
+                && cmd.get_arguments().any(|opt| {
+                    opt.get_short() == shorts.chars().next() && opt.get_action().takes_values()
+                });
+            escaped.push(arg);
+            if takes_value {
+                escaped.extend(args.next());
+            }
+            continue;
+        }
+        match targets.as_mut() {
+            Some(targets) => targets.push(arg),
+            None => {
+                if let Some(subcmd) = cmd.find_subcommand(s) {
+                    cmd = subcmd;
+                    targets = takes_variants(cmd).then(Vec::new);
+                }
+                escaped.push(arg);
+            }
+        }
+    }
+    if let Some(targets) = targets.filter(|targets| !targets.is_empty()) {
+        escaped.push("--".into());
+        escaped.extend(targets);
+    }
+    escaped
+}
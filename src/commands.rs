@@ -14,25 +14,41 @@ pub trait Command {
 }
 
 mod alias;
+mod audit;
+mod cache;
 mod completions;
 mod current;
 mod default;
+mod doctor;
+mod info;
 mod init;
 mod install;
 mod list_local;
 mod list_remote;
+mod logs;
+mod outdated;
+mod reinstall;
 mod unalias;
 mod uninstall;
+mod upgrade;
 mod r#use;
 
 pub use alias::Alias;
+pub use audit::Audit;
+pub use cache::Cache;
 pub use completions::Completions;
 pub use current::Current;
 pub use default::Default;
+pub use doctor::Doctor;
+pub use info::Info;
 pub use init::Init;
 pub use install::Install;
 pub use list_local::ListLocal;
 pub use list_remote::ListRemote;
+pub use logs::Logs;
+pub use outdated::Outdated;
 pub use r#use::Use;
+pub use reinstall::Reinstall;
 pub use unalias::Unalias;
 pub use uninstall::Uninstall;
+pub use upgrade::Upgrade;
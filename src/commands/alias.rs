@@ -22,11 +22,11 @@ impl Command for Alias {
 
     fn run(&self, config: &Config) -> Result<(), Error> {
         if !Local::local(config).any(|local| match local {
-            Local::Installed(installed_version) => match self.link_version {
+            Local::Installed(installed_version) => match &self.link_version {
                 Local::Installed(link_version) => link_version.includes(&installed_version),
-                Local::System => false,
+                Local::Labeled(_) | Local::System => false,
             },
-            Local::System => self.link_version == Local::System,
+            local => self.link_version == local,
         }) {
             println!(
                 "{}: Version '{}' does not exist",
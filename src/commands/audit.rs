@@ -0,0 +1,167 @@
+use super::outdated::{fetch_releases_of, newer_patches};
+use super::{Command, Config};
+use crate::decorized::Decorized;
+use crate::release::{self, Release, Schedule, Support};
+use crate::version::{self, Version};
+use colored::Colorize;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+pub struct Audit {
+    /// Print the findings as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    FailedFetchRelease(#[from] release::FetchError),
+
+    #[error(transparent)]
+    FailedLoadSchedule(#[from] release::schedule::Error),
+
+    #[error("{0} installed version(s) are vulnerable or end of life")]
+    Vulnerable(usize),
+}
+
+/// Installed version superseded by security releases or past its end of life
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct Finding {
+    version: Version,
+    /// Newer patch releases tagged as security fixes
+    security_releases: Vec<Version>,
+    end_of_life: bool,
+}
+
+impl Command for Audit {
+    type Error = Error;
+
+    fn run(&self, config: &Config) -> Result<(), Error> {
+        let installed = version::installed(config)
+            .filter(|version| !version.is_pre_release())
+            .collect_vec();
+        let releases = fetch_releases_of(config, &installed)?;
+        // JSON is read by CI, which mustn't pass on a schedule other than the one given
+        let schedule = match config.try_support_schedule() {
+            Ok(schedule) => schedule,
+            Err(e) if self.json => return Err(e.into()),
+            Err(e) => {
+                eprintln!(
+                    "{}: {}; End of life is checked by the embedded schedule, which may be out of date",
+                    "warning".yellow().bold(),
+                    e
+                );
+                Schedule::embedded()
+            }
+        };
+        let findings = installed
+            .into_iter()
+            .filter_map(|version| audit(version, &releases, &schedule))
+            .collect_vec();
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&findings).unwrap());
+        } else {
+            for finding in &findings {
+                if !finding.security_releases.is_empty() {
+                    println!(
+                        "{:>12} {} is superseded by security releases {}",
+                        "Vulnerable".red().bold(),
+                        finding.version.decorized_with_prefix(),
+                        finding.security_releases.iter().join(", ")
+                    );
+                }
+                if finding.end_of_life {
+                    println!(
+                        "{:>12} {} no longer receives security fixes",
+                        "End of life".yellow().bold(),
+                        finding.version.decorized_with_prefix(),
+                    );
+                }
+            }
+        }
+        if findings.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Vulnerable(findings.len()))
+        }
+    }
+}
+
+fn audit(
+    version: Version,
+    releases: &BTreeMap<Version, Release>,
+    schedule: &Schedule,
+) -> Option<Finding> {
+    let security_releases = newer_patches(version, releases)
+        .filter(|(_, release)| release.is_security())
+        .map(|(release_version, _)| *release_version)
+        .collect_vec();
+    let end_of_life = schedule.support(version) == Some(Support::EndOfLife);
+    (!security_releases.is_empty() || end_of_life).then_some(Finding {
+        version,
+        security_releases,
+        end_of_life,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_vulnerable() {
+        let releases: BTreeMap<Version, Release> = serde_json::from_str(
+            r#"{
+                "5.6.0": {"date": "28 Aug 2014", "tags": [], "source": [], "version": "5.6.0"},
+                "5.6.40": {"date": "10 Jan 2019", "tags": ["security"], "source": [], "version": "5.6.40"},
+                "8.1.12": {"date": "27 Oct 2022", "tags": [], "source": [], "version": "8.1.12"},
+                "8.1.13": {"date": "24 Nov 2022", "tags": ["security"], "source": [], "version": "8.1.13"},
+                "8.1.14": {"date": "05 Jan 2023", "tags": [], "source": [], "version": "8.1.14"}
+            }"#,
+        )
+        .unwrap();
+        let schedule = Schedule::parse(
+            "test",
+            br#"[
+                { "cycle": "8.1", "releaseDate": "2021-11-25", "support": "2023-11-25", "eol": false },
+                { "cycle": "5.6", "releaseDate": "2014-08-28", "support": "2017-01-19", "eol": "2018-12-31" }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            audit("5.6.40".parse().unwrap(), &releases, &schedule),
+            Some(Finding {
+                version: "5.6.40".parse().unwrap(),
+                security_releases: vec![],
+                end_of_life: true,
+            })
+        );
+        assert_eq!(
+            audit("8.1.12".parse().unwrap(), &releases, &schedule),
+            Some(Finding {
+                version: "8.1.12".parse().unwrap(),
+                security_releases: vec!["8.1.13".parse().unwrap()],
+                end_of_life: false,
+            })
+        );
+        assert_eq!(audit("8.1.14".parse().unwrap(), &releases, &schedule), None);
+    }
+
+    #[test]
+    fn unavailable_schedule() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_support_schedule(base_dir.path().join("php.json").to_str().unwrap());
+        assert!(Audit { json: false }.run(&config).is_ok());
+        assert!(matches!(
+            Audit { json: true }.run(&config),
+            Err(Error::FailedLoadSchedule(_))
+        ));
+    }
+}
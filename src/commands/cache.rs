@@ -0,0 +1,103 @@
+use super::{Command, Config};
+use crate::cache::{DownloadCache, Period};
+use crate::decorized::Decorized;
+use chrono::{Local, TimeZone};
+use colored::Colorize;
+use indicatif::HumanBytes;
+use itertools::Itertools;
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+#[command(disable_help_subcommand = true)]
+pub struct Cache {
+    #[clap(subcommand)]
+    subcmd: CacheCommand,
+}
+
+// completions look up nested subcommands by the full path in their `bin_name`
+#[derive(clap::Subcommand, Debug)]
+enum CacheCommand {
+    /// List cached source archives
+    #[command(bin_name = "phpup cache list", visible_aliases = &["ls"])]
+    List,
+
+    /// Remove all cached source archives and release lists
+    #[command(bin_name = "phpup cache clean")]
+    Clean,
+
+    /// Remove cached source archives which have not been used for a while
+    #[command(bin_name = "phpup cache prune")]
+    Prune {
+        /// Remove archives not used within the period (e.g. `30d`, `12h`, `2w`)
+        #[arg(long, value_name = "PERIOD")]
+        older_than: Period,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't remove the cache: {0}")]
+    FailedRemove(#[from] std::io::Error),
+}
+
+impl Command for Cache {
+    type Error = Error;
+
+    fn run(&self, config: &Config) -> Result<(), Error> {
+        let cache_dir = config.cache_dir();
+        let cache = DownloadCache::new(&cache_dir);
+        let entries = cache
+            .entries()
+            .sorted_by(|a, b| a.filename.cmp(&b.filename))
+            .collect_vec();
+
+        match &self.subcmd {
+            CacheCommand::List => {
+                for entry in &entries {
+                    println!(
+                        "{:<24} {:>10}  {}  {}",
+                        entry.filename,
+                        HumanBytes(entry.size).to_string(),
+                        entry.last_used.format("%Y-%m-%d %H:%M"),
+                        entry.checksum.dimmed()
+                    );
+                }
+                let total = entries.iter().map(|entry| entry.size).sum();
+                println!(
+                    "{} archives, {} in {}",
+                    entries.len(),
+                    HumanBytes(total),
+                    cache_dir.display().decorized()
+                );
+            }
+            CacheCommand::Clean | CacheCommand::Prune { .. } => {
+                let deadline = match &self.subcmd {
+                    // a period beyond the dates chrono can represent keeps every archive
+                    CacheCommand::Prune { older_than } => Some(
+                        Local::now()
+                            .checked_sub_signed(older_than.0)
+                            .unwrap_or_else(|| Local.timestamp(0, 0)),
+                    ),
+                    _ => None,
+                };
+                let mut removed = 0;
+                let mut freed = 0;
+                for entry in entries {
+                    if deadline.is_some_and(|deadline| entry.last_used >= deadline) {
+                        continue;
+                    }
+                    cache.remove(&entry)?;
+                    println!("{:>12} {}", "Removed".green().bold(), entry.filename);
+                    removed += 1;
+                    freed += entry.size;
+                }
+                println!("{} archives, {} removed", removed, HumanBytes(freed));
+                if matches!(self.subcmd, CacheCommand::Clean) {
+                    config.release_cache().clear()?;
+                    println!("{:>12} cached release lists", "Removed".green().bold());
+                }
+            }
+        }
+        Ok(())
+    }
+}
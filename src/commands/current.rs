@@ -14,6 +14,7 @@ impl Command for Current {
     fn run(&self, config: &Config) -> Result<(), Error> {
         match Local::current(config) {
             Some(Local::Installed(version)) => println!("{}", version),
+            Some(Local::Labeled(label)) => println!("{}", label),
             Some(Local::System) => println!("system"),
             None => println!("none"),
         }
@@ -18,10 +18,10 @@ impl Command for Default {
 
     fn run(&self, config: &Config) -> Result<(), Error> {
         let alias = crate::version::Alias::default();
-        if let Some(link_version) = self.link_version {
+        if let Some(link_version) = &self.link_version {
             let cmd = super::Alias {
                 alias,
-                link_version,
+                link_version: link_version.clone(),
             };
             cmd.run(config).map_err(Into::into)
         } else {
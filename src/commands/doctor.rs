@@ -0,0 +1,114 @@
+use super::install::deps::{self, Kind};
+use super::{Command, Config};
+use crate::variant::{self, Variant};
+use crate::version::Version;
+use colored::Colorize;
+use itertools::Itertools;
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+pub struct Doctor {
+    /// Check the tools and libraries needed to build the version (e.g. `8.2`)
+    #[arg(long, value_name = "VERSION")]
+    build_deps: Version,
+
+    /// Bundled extensions to be enabled or disabled (e.g. `+intl +gd -cgi`)
+    #[arg(value_name = "+VARIANT | -VARIANT")]
+    variants: Vec<Variant>,
+
+    /// Configure options to be used
+    #[arg(long, env = "PHPUP_CONFIGURE_OPTS", allow_hyphen_values = true)]
+    configure_opts: Option<String>,
+
+    /// Also check the tools to build from a php-src git repository
+    #[arg(long)]
+    git: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    InvalidVariant(#[from] variant::Error),
+
+    #[error("{0} build dependencies are missing")]
+    Missing(usize),
+}
+
+impl Command for Doctor {
+    type Error = Error;
+
+    fn run(&self, _: &Config) -> Result<(), Error> {
+        let configure_opts = variant::configure_opts(&self.variants, self.build_deps)?
+            .into_iter()
+            .chain(
+                self.configure_opts
+                    .iter()
+                    .flat_map(|opts| opts.split_whitespace())
+                    .map(ToOwned::to_owned),
+            )
+            .collect_vec();
+        let configure_opts = configure_opts.iter().map(String::as_str).collect_vec();
+        let requirements = deps::requirements(self.build_deps, &configure_opts, self.git);
+
+        for dependency in &requirements {
+            let kind = match dependency.kind {
+                Kind::Tool => "command",
+                Kind::Module => "pkg-config module",
+            };
+            if dependency.is_found(None) {
+                println!(
+                    "{:>12} {} {}",
+                    "Found".green().bold(),
+                    dependency.name,
+                    kind
+                );
+            } else {
+                println!(
+                    "{:>12} {} {}",
+                    "Missing".red().bold(),
+                    dependency.name,
+                    kind
+                );
+            }
+        }
+        let missing = deps::Missing::check(requirements, None);
+        if missing.is_empty() {
+            return Ok(());
+        }
+        match missing.install_command() {
+            Some(command) => println!("\nInstall them by:\n    {}", command),
+            None => println!(
+                "\n{}: Unknown distro; install the packages providing them",
+                "warning".yellow().bold()
+            ),
+        }
+        Err(Error::Missing(missing.dependencies.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::escape_variants;
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn parse_options() {
+        let parse = |args: &[&str]| {
+            Doctor::try_parse_from(escape_variants(&Doctor::command(), args.iter().copied()))
+        };
+        let doctor = parse(&["doctor", "--git", "--build-deps", "8.2", "+intl", "-cgi"]).unwrap();
+        assert!(doctor.git);
+        assert_eq!(doctor.variants.len(), 2);
+        // the same rule as `install`
+        let doctor = parse(&["doctor", "-cgi", "--build-deps", "8.2", "+intl"]).unwrap();
+        assert_eq!(doctor.variants.len(), 2);
+        assert!(Doctor::try_parse_from(["doctor"]).is_err());
+        assert!(matches!(
+            Doctor::try_parse_from(["doctor", "--build-deps", "7.1", "+sodium"])
+                .unwrap()
+                .run(&Config::default()),
+            Err(Error::InvalidVariant(_))
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+use super::{Command, Config};
+use crate::decorized::Decorized;
+use crate::manifest::{self, Manifest};
+use crate::version::{self, Local};
+use colored::Colorize;
+use indicatif::HumanDuration;
+use itertools::Itertools;
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+pub struct Info {
+    /// Installed version, label or system (e.g. `8.1`, `8.4-dev`)
+    version: Local,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't find installed version '{0}'")]
+    NotInstalled(Local),
+
+    #[error(transparent)]
+    FailedReadManifest(#[from] manifest::Error),
+
+    #[error("Can't execute {command}: {source}")]
+    FailedExecute {
+        command: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl Command for Info {
+    type Error = Error;
+
+    fn run(&self, config: &Config) -> Result<(), Error> {
+        let not_installed = || Error::NotInstalled(self.version.clone());
+        let (local, bin_dir) = match &self.version {
+            Local::System => {
+                let dir = version::system::path().ok_or_else(not_installed)?;
+                (Local::System, dir)
+            }
+            local => {
+                let installed = local.installed(config).ok_or_else(not_installed)?;
+                let dir = config
+                    .versions_dir()
+                    .join(installed.to_string())
+                    .join("bin");
+                (installed, dir)
+            }
+        };
+
+        println!("{}", local.decorized_with_prefix().bold());
+        let install_dir = bin_dir.parent().unwrap();
+        print_field("Path", install_dir.display().decorized());
+        if local != Local::System {
+            match Manifest::read(install_dir)? {
+                Some(manifest) => print_manifest(&manifest),
+                None => print_field("Manifest", "none; installed by an older PHP-UP"),
+            }
+        }
+
+        let php = bin_dir.join("php");
+        println!();
+        print_output(&php, "-v")?;
+        println!();
+        print_output(&php, "-m")?;
+        Ok(())
+    }
+}
+
+fn print_field(name: &str, value: impl std::fmt::Display) {
+    println!("{:>12}   {}", name.bold(), value);
+}
+
+fn print_manifest(manifest: &Manifest) {
+    print_field("Version", manifest.version);
+    if !manifest.variants.is_empty() {
+        let variants = manifest.variants.iter().map(ToString::to_string);
+        print_field("Variants", itertools::join(variants, " "));
+    }
+    if !manifest.configure_opts.is_empty() {
+        print_field("Configure", manifest.configure_opts.join(" "));
+    }
+    if let Some(source_url) = &manifest.source_url {
+        print_field("Source", source_url);
+    }
+    if let Some(git) = &manifest.git {
+        print_field(
+            "Git",
+            format!("{} {} ({})", git.repository, git.reference, git.commit),
+        );
+    }
+    if let Some(verification) = &manifest.verification {
+        let checksum = match &verification.checksum {
+            Some(checksum) if verification.user_supplied_checksum => {
+                format!("{} (--checksum)", checksum)
+            }
+            Some(checksum) => checksum.to_string(),
+            None => "none".to_owned(),
+        };
+        print_field(
+            "Checksum",
+            format!("{} (policy: {})", checksum, verification.checksum_policy),
+        );
+        if let Some(signer) = &verification.signer {
+            print_field("Signed by", signer);
+        }
+    }
+    if let Some(built_at) = manifest.built_at {
+        let built_at = built_at.with_timezone(&chrono::Local);
+        print_field("Built at", built_at.format("%Y-%m-%d %H:%M:%S %:z"));
+    }
+    if let Some(build_seconds) = manifest.build_seconds {
+        print_field(
+            "Build time",
+            HumanDuration(Duration::from_secs(build_seconds)),
+        );
+    }
+    if !manifest.toolchain.is_empty() {
+        print_field("Toolchain", toolchain(&manifest.toolchain));
+    }
+    if let Some(compiler) = &manifest.compiler {
+        print_field("Compiler", compiler);
+    }
+    if let Some(phpup_version) = &manifest.phpup_version {
+        print_field("PHP-UP", phpup_version);
+    }
+}
+
+/// e.g. `CC=clang CFLAGS='-O2 -g' (profile: clang, ccache)`
+fn toolchain(toolchain: &manifest::Toolchain) -> String {
+    let vars = [
+        ("CC", &toolchain.cc),
+        ("CXX", &toolchain.cxx),
+        ("CFLAGS", &toolchain.cflags),
+        ("LDFLAGS", &toolchain.ldflags),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| {
+        let value = value.as_deref()?;
+        Some(if value.contains(char::is_whitespace) {
+            format!("{}='{}'", name, value)
+        } else {
+            format!("{}={}", name, value)
+        })
+    })
+    .join(" ");
+    let notes = toolchain
+        .profile
+        .iter()
+        .map(|profile| format!("profile: {}", profile))
+        .chain(toolchain.ccache.then(|| "ccache".to_owned()))
+        .join(", ");
+    match (vars.is_empty(), notes.is_empty()) {
+        (_, true) => vars,
+        (true, false) => format!("({})", notes),
+        (false, false) => format!("{} ({})", vars, notes),
+    }
+}
+
+/// Prints the output of `php <arg>`
+fn print_output(php: &Path, arg: &str) -> Result<(), Error> {
+    let output = std::process::Command::new(php)
+        .arg(arg)
+        .output()
+        .map_err(|source| Error::FailedExecute {
+            command: format!("{} {}", php.display(), arg),
+            source,
+        })?;
+    print!("{}", String::from_utf8_lossy(&output.stdout));
+    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn info_of_installed() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let config = Config::default().with_base_dir(&base_dir);
+        let install_dir = config.versions_dir().join("8.2.10");
+        fs::create_dir_all(install_dir.join("bin")).unwrap();
+        let php = install_dir.join("bin").join("php");
+        fs::write(&php, "#!/bin/sh\necho 'PHP 8.2.10'\n").unwrap();
+        fs::set_permissions(&php, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let info = |version: &str| {
+            Info {
+                version: version.parse().unwrap(),
+            }
+            .run(&config)
+        };
+        // without a manifest
+        info("8.2").unwrap();
+        Manifest::new("8.2.10".parse().unwrap())
+            .write(&install_dir)
+            .unwrap();
+        info("8.2.10").unwrap();
+        assert!(matches!(info("8.1"), Err(Error::NotInstalled(_))));
+        assert!(matches!(info("8.4-dev"), Err(Error::NotInstalled(_))));
+    }
+}
@@ -72,24 +72,23 @@ fn generate_symlink_path() -> PathBuf {
 }
 
 fn default_path(config: &Config) -> Option<PathBuf> {
-    if let Ok(Local::Installed(version)) = Alias::default().resolve(config.aliases_dir()) {
-        if let Some(installed) = version::latest_installed_by(&version, config) {
-            Some(
-                config
-                    .versions_dir()
-                    .join(installed.to_string())
-                    .join("bin"),
-            )
-        } else {
-            println!(
-                "echo \"warning: Version '{}' which is specified as default does not exist\"",
-                version
-            );
-            None
-        }
-    } else {
-        system::path()
+    let default_version = match Alias::default().resolve(config.aliases_dir()) {
+        Ok(Local::System) | Err(_) => return system::path(),
+        Ok(local) => local,
+    };
+    let installed_dir = match &default_version {
+        Local::Installed(version) => version::latest_installed_by(version, config)
+            .map(|installed| config.versions_dir().join(installed.to_string())),
+        _ => Some(config.versions_dir().join(default_version.to_string()))
+            .filter(|dir| dir.join("bin").join("php").is_file()),
+    };
+    if installed_dir.is_none() {
+        println!(
+            "echo \"warning: Version '{}' which is specified as default does not exist\"",
+            default_version
+        );
     }
+    installed_dir.map(|dir| dir.join("bin"))
 }
 
 #[cfg(test)]
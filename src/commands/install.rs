@@ -1,22 +1,37 @@
+pub(super) mod deps;
+mod download;
+mod git;
+mod jobs;
 mod make;
 mod progress_reader;
 
 use super::{Command, Config};
-use crate::curl;
+use crate::archive;
+use crate::cache::DownloadCache;
+use crate::clap_enum_variants;
 use crate::decorized::Decorized;
-use crate::release::{self, Hash};
-use crate::version::{self, Version};
+use crate::http::Transport;
+use crate::manifest::{self, Manifest};
+use crate::parallel;
+use crate::release::{self, ChecksumPolicy, Hash, Release};
+use crate::signature;
+use crate::variant::{self, Variant};
+use crate::version::{self, semantic, Local, Version};
 use colored::Colorize;
-use flate2::read::GzDecoder;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar, ProgressStyle};
+use itertools::Itertools;
 use once_cell::sync::Lazy;
 use progress_reader::ProgressReader;
+use std::fmt::Display;
 use std::fs;
-use std::io::{BufReader, BufWriter, Read};
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
-use tar::Archive;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+const MAX_PARALLEL_DOWNLOADS: usize = 3;
+
 static PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
     let progress_template =
         "{prefix:>12.cyan.bold} [{bar:25}] {bytes}/{total_bytes} ({eta}) {wide_msg}";
@@ -26,9 +41,12 @@ static PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
         .progress_chars("=> ")
 });
 
-#[derive(clap::Parser, Debug)]
+#[derive(clap::Parser, Debug, Default)]
 pub struct Install {
-    version: Option<Version>,
+    /// Versions to install, and bundled extensions to enable or disable in all of them
+    /// (e.g. `8.1 8.2 +intl +gd +pgsql -cgi`)
+    #[arg(value_name = "VERSION | +VARIANT | -VARIANT")]
+    targets: Vec<Target>,
 
     #[clap(flatten)]
     version_file: version::File,
@@ -37,6 +55,121 @@ pub struct Install {
     /// To specify two or more options, enclose them with quotation marks.
     #[arg(long, env = "PHPUP_CONFIGURE_OPTS", allow_hyphen_values = true)]
     configure_opts: Option<String>,
+
+    /// Verify the OpenPGP signatures of the source archives by the keyring (`--keyring`)
+    /// [default: optional]
+    #[arg(
+        long,
+        env = "PHPUP_VERIFY_SIGNATURE",
+        value_name = "POLICY",
+        value_parser = clap_enum_variants!(signature::Policy)
+    )]
+    verify_signature: Option<signature::Policy>,
+
+    /// Refuse source archives without a checksum (`strict`) or a SHA-256 checksum (`sha256-only`)
+    /// [default: warn]
+    #[arg(
+        long,
+        env = "PHPUP_CHECKSUM_POLICY",
+        value_name = "POLICY",
+        value_parser = clap_enum_variants!(ChecksumPolicy)
+    )]
+    checksum_policy: Option<ChecksumPolicy>,
+
+    /// Verify the source archive by this checksum instead of the one of the release
+    #[arg(long, value_name = "sha256:<hex>")]
+    checksum: Option<Hash>,
+
+    /// Install from a local source archive instead of downloading a release
+    #[arg(long, value_name = "FILE", conflicts_with = "from_source")]
+    from_tarball: Option<PathBuf>,
+
+    /// Install from an unpacked source directory such as a patched php-src
+    #[arg(long, value_name = "DIR", conflicts_with = "checksum")]
+    from_source: Option<PathBuf>,
+
+    /// Install `--from-source` even if `--verify-signature=required` or the checksum policy
+    /// requires sources to be verified
+    #[arg(long, requires = "from_source")]
+    allow_unverified: bool,
+
+    /// Build from a php-src git repository instead of a release
+    #[arg(
+        long,
+        value_name = "URL | PATH",
+        conflicts_with_all = ["from_tarball", "from_source", "checksum"]
+    )]
+    git: Option<String>,
+
+    /// Branch, tag or commit of `--git` to build [default: the default branch]
+    #[arg(long = "ref", value_name = "REF", requires = "git")]
+    git_ref: Option<String>,
+
+    /// Name a local source install instead of by its version (e.g. `8.4-dev`).
+    /// `--git` installs are named `X.Y-dev` for a `PHP-X.Y` branch, or `<ref>-<short commit>`
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Build with the compilers and flags of a profile in the config file
+    #[arg(long, env = "PHPUP_PROFILE", value_name = "NAME")]
+    profile: Option<String>,
+
+    /// C compiler to build with (`CC`), overriding the one of `--profile`
+    #[arg(long, value_name = "COMMAND")]
+    cc: Option<String>,
+
+    /// C++ compiler to build with (`CXX`), overriding the one of `--profile`
+    #[arg(long, value_name = "COMMAND")]
+    cxx: Option<String>,
+
+    /// Flags of the C compiler (`CFLAGS`), overriding the ones of `--profile`
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true)]
+    cflags: Option<String>,
+
+    /// Flags of the linker (`LDFLAGS`), overriding the ones of `--profile`
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true)]
+    ldflags: Option<String>,
+}
+
+/// Source of `--from-tarball`, `--from-source` or `--git`
+#[derive(Clone, Copy)]
+enum LocalSource<'a> {
+    Tarball(&'a Path),
+    Directory(&'a Path),
+    Git(&'a str),
+}
+
+/// How to verify the signatures of source archives
+#[derive(Clone, Copy)]
+struct SignatureCheck<'a> {
+    policy: signature::Policy,
+    keyring: &'a Path,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Version(Version),
+    Variant(Variant),
+}
+
+#[derive(Error, Debug)]
+enum ParseTargetError {
+    #[error(transparent)]
+    InvalidVersion(#[from] semantic::ParseError),
+
+    #[error(transparent)]
+    InvalidVariant(#[from] variant::ParseError),
+}
+
+impl FromStr for Target {
+    type Err = ParseTargetError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with(['+', '-']) {
+            Ok(Target::Variant(s.parse()?))
+        } else {
+            Ok(Target::Version(s.parse()?))
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -50,18 +183,64 @@ pub enum Error {
     #[error("PHP3 installation is not supported yet")]
     UnsupportedPHP3,
 
+    #[error("--checksum can't be used to install several versions")]
+    ChecksumForSeveralVersions,
+
+    #[error("Versions can't be specified with --from-tarball, --from-source or --git")]
+    VersionWithLocalSource,
+
+    #[error("--label can be used only with --from-tarball, --from-source or --git")]
+    LabelWithoutLocalSource,
+
+    #[error(
+        "Invalid label '{0}'; it must be letters, digits and `._+` joined by `-`, e.g. `8.4-dev`"
+    )]
+    InvalidLabel(String),
+
+    #[error("{0} can't be verified, which the signature or checksum policy requires; Pass `--allow-unverified` to install it anyway")]
+    UnverifiedSource(PathBuf),
+
+    #[error("Unknown profile '{0}'; define it as `[profiles.{0}]` in the config file")]
+    UnknownProfile(String),
+
+    #[error(
+        "Can't detect the PHP version of {0}; PHP_VERSION isn't defined in main/php_version.h"
+    )]
+    UnknownSourceVersion(PathBuf),
+
+    #[error(transparent)]
+    InvalidVariant(#[from] variant::Error),
+
     #[error(transparent)]
     FailedFetchRelease(#[from] release::FetchError),
 
     #[error(transparent)]
-    FailedDownload(#[from] curl::Error),
+    FailedDownload(#[from] download::Error),
 
     #[error(transparent)]
     InvalidChecksum(#[from] release::ChecksumError),
 
+    #[error(transparent)]
+    InvalidSignature(#[from] signature::Error),
+
+    #[error(transparent)]
+    FailedGit(#[from] git::Error),
+
+    #[error("Source {0} of the previous build doesn't exist anymore")]
+    SourceNotFound(PathBuf),
+
+    #[error("Unknown format of the source archive: {0}")]
+    UnknownArchiveFormat(PathBuf),
+
     #[error(transparent)]
     FailedMake(#[from] make::Error),
 
+    #[error("Missing build dependencies: {0}\nUse `--skip-preflight` to build anyway")]
+    MissingBuildDeps(deps::Missing),
+
+    #[error(transparent)]
+    FailedWriteManifest(#[from] manifest::Error),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
@@ -70,59 +249,336 @@ impl Command for Install {
     type Error = Error;
 
     fn run(&self, config: &Config) -> Result<(), Error> {
-        let request_version = self
-            .version
-            .map_or_else(|| self.get_version_from_version_file(), Ok)?;
+        if let Some(local_source) = self.local_source() {
+            if !self.versions().is_empty() {
+                return Err(Error::VersionWithLocalSource);
+            }
+            return self.install_local(config, local_source, None);
+        }
+        if self.label.is_some() {
+            return Err(Error::LabelWithoutLocalSource);
+        }
 
-        if request_version.major_version() == 3 {
+        let request_versions = match self.versions() {
+            versions if versions.is_empty() => vec![self.get_version_from_version_file()?],
+            versions => versions,
+        };
+
+        if request_versions
+            .iter()
+            .any(|version| version.major_version() == 3)
+        {
             return Err(Error::UnsupportedPHP3);
         }
+        if self.checksum.is_some() && request_versions.len() > 1 {
+            return Err(Error::ChecksumForSeveralVersions);
+        }
+
+        let toolchain = self.toolchain(config)?;
+        let http = config.http();
+        let release_source = config.release_source(&*http);
+        let variants = self.variants();
+        let mut plans: Vec<(Release, Manifest)> = Vec::new();
+        for request_version in request_versions {
+            let release = release_source.fetch_latest(request_version)?;
+            let install_version = release.version.unwrap();
+
+            if version::latest_installed_by(&request_version, config) == Some(install_version) {
+                println!(
+                    "{}: Already installed {}",
+                    "warning".yellow().bold(),
+                    install_version.decorized_with_prefix()
+                );
+                continue;
+            }
+            if plans
+                .iter()
+                .any(|(planned, _)| planned.version == Some(install_version))
+            {
+                continue;
+            }
+            let mut manifest = Manifest::new(install_version);
+            manifest.configure_opts = self.configure_opts_for(&variants, install_version)?;
+            manifest.variants = variants.clone();
+            manifest.toolchain = toolchain.clone();
+            plans.push((release, manifest));
+        }
+        self.install_releases(config, plans)
+    }
+}
+
+impl Install {
+    /// Downloads the releases at once, and builds them one after another with their manifests,
+    /// which have the variants and configure options to build with
+    pub(super) fn install_releases(
+        &self,
+        config: &Config,
+        plans: Vec<(Release, Manifest)>,
+    ) -> Result<(), Error> {
+        for (_, manifest) in &plans {
+            println!(
+                "{:>12} {}",
+                "Installing".green().bold(),
+                manifest.local().decorized_with_prefix()
+            );
+        }
+        // release archives come with `configure`
+        for (_, manifest) in &plans {
+            preflight(config, manifest, false)?;
+        }
+
+        let http = config.http();
+
+        let download_dir = tempfile::Builder::new()
+            .prefix(".downloads-")
+            .tempdir_in(config.base_dir())?;
+        let (mirrors, museum_mirrors) = (config.mirrors(), config.museum_mirrors());
+        let qa_mirrors = config.qa_mirrors();
+        let archive_formats = config.archive_formats();
+        let cache = DownloadCache::new(config.cache_dir());
+        let keyring = config.keyring();
+        let signature_check = self.signature_check(config, &keyring);
+        let checksum_policy = self.checksum_policy(config);
+        let progress = MultiProgress::new();
+
+        // download several versions at once, and build them one after another as they arrive
+        parallel::for_each_ordered(
+            plans,
+            MAX_PARALLEL_DOWNLOADS,
+            |(release, mut manifest)| {
+                let source =
+                    release.source_urls(archive_formats, &mirrors, &museum_mirrors, &qa_mirrors)?;
+                let checksum = self.checksum.as_ref().or(source.checksum);
+                checksum_policy.check(source.filename, checksum)?;
+                let fetched = fetch_source(
+                    &*http,
+                    &progress,
+                    &source,
+                    &download_dir,
+                    checksum,
+                    &cache,
+                    signature_check,
+                )?;
+                manifest.source_url = Some(fetched.url);
+                manifest.verification = Some(manifest::Verification {
+                    checksum_policy,
+                    checksum: checksum.cloned(),
+                    user_supplied_checksum: self.checksum.is_some(),
+                    signer: fetched.signer.map(|signer| signer.to_string()),
+                });
+                Ok((fetched.path, manifest))
+            },
+            |fetched: Result<_, Error>| {
+                let (archive, manifest) = fetched?;
+                let source_dir = unpack(&progress, &archive, &download_dir)?;
+                install(&progress, &source_dir, config, manifest)
+            },
+        )
+    }
 
-        let release = release::fetch_latest(request_version)?;
-        let install_version = release.version.unwrap();
+    fn local_source(&self) -> Option<LocalSource<'_>> {
+        self.from_tarball
+            .as_deref()
+            .map(LocalSource::Tarball)
+            .or_else(|| self.from_source.as_deref().map(LocalSource::Directory))
+            .or_else(|| self.git.as_deref().map(LocalSource::Git))
+    }
+    /// Installs `--from-tarball`, `--from-source` or `--git`, whose version is read from the source.
+    /// The options recorded in `previous` are used instead of the variants and `--configure-opts`.
+    fn install_local(
+        &self,
+        config: &Config,
+        local_source: LocalSource,
+        previous: Option<&Manifest>,
+    ) -> Result<(), Error> {
+        let toolchain = match previous {
+            Some(previous) => previous.toolchain.clone(),
+            None => self.toolchain(config)?,
+        };
+        let work_dir = tempfile::Builder::new()
+            .prefix(".downloads-")
+            .tempdir_in(config.base_dir())?;
+        let progress = MultiProgress::new();
+        let mut git_source = None;
+        let (source_dir, verification) = match local_source {
+            LocalSource::Tarball(tarball) => {
+                let keyring = config.keyring();
+                let checksum_policy = self.checksum_policy(config);
+                let filename = tarball
+                    .file_name()
+                    .map(|filename| filename.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                checksum_policy.check(&filename, self.checksum.as_ref())?;
+                let signer = verify(
+                    &progress,
+                    tarball,
+                    self.checksum.as_ref(),
+                    self.signature_check(config, &keyring),
+                )?;
+                let verification = manifest::Verification {
+                    checksum_policy,
+                    checksum: self.checksum.clone(),
+                    user_supplied_checksum: self.checksum.is_some(),
+                    signer: signer.map(|signer| signer.to_string()),
+                };
+                (unpack(&progress, tarball, &work_dir)?, Some(verification))
+            }
+            LocalSource::Directory(dir) => {
+                let checksum_policy = self.checksum_policy(config);
+                let keyring = config.keyring();
+                let requires_verification = checksum_policy.check("", None).is_err()
+                    || self.signature_check(config, &keyring).policy == signature::Policy::Required;
+                if requires_verification {
+                    if !self.allow_unverified {
+                        return Err(Error::UnverifiedSource(dir.to_owned()));
+                    }
+                    log(
+                        &progress,
+                        format!(
+                            "{}: Installing {} without verification",
+                            "warning".yellow().bold(),
+                            dir.display()
+                        ),
+                    );
+                }
+                let verification = manifest::Verification {
+                    checksum_policy,
+                    checksum: None,
+                    user_supplied_checksum: false,
+                    signer: None,
+                };
+                (dir.to_owned(), Some(verification))
+            }
+            LocalSource::Git(repository) => {
+                // the rest is checked once the version is known from the checkout
+                if !config.skip_preflight() {
+                    let missing = deps::Missing::check(deps::tools(true), toolchain.cc.as_deref());
+                    if !missing.is_empty() {
+                        return Err(Error::MissingBuildDeps(missing));
+                    }
+                }
+                let source_dir = work_dir.path().join("php-src");
+                fs::create_dir(&source_dir)?;
+                let source = checkout(&progress, repository, self.git_ref.as_deref(), &source_dir)?;
+                git_source = Some(source);
+                (source_dir, None)
+            }
+        };
+
+        let install_version = source_version(&source_dir)?;
+        let mut manifest = Manifest::new(install_version);
+        manifest.label = self
+            .label
+            .clone()
+            .or_else(|| git_source.as_ref().map(default_label));
+        if let Some(label) = &manifest.label {
+            if !matches!(label.parse(), Ok(Local::Labeled(_))) {
+                return Err(Error::InvalidLabel(label.clone()));
+            }
+        }
+        manifest.source_url = match local_source {
+            LocalSource::Tarball(path) | LocalSource::Directory(path) => path
+                .canonicalize()
+                .ok()
+                .map(|path| path.display().to_string()),
+            LocalSource::Git(_) => None,
+        };
+        manifest.git = git_source;
 
-        if version::latest_installed_by(&request_version, config) == Some(install_version) {
+        if config.versions_dir().join(manifest.install_name()).exists() {
             println!(
                 "{}: Already installed {}",
                 "warning".yellow().bold(),
-                install_version.decorized_with_prefix()
+                manifest.local().decorized_with_prefix()
             );
             return Ok(());
         }
         println!(
             "{:>12} {}",
             "Installing".green().bold(),
-            install_version.decorized_with_prefix()
+            manifest.local().decorized_with_prefix()
         );
 
-        let install_dir = config.versions_dir().join(install_version.to_string());
-        let download_dir = tempfile::Builder::new()
-            .prefix(".downloads-")
-            .tempdir_in(&config.base_dir())?;
-
-        let (url, checksum) = release.source_url();
-
-        let tar_gz = download(&url, &download_dir)?;
-        verify(&tar_gz, checksum)?;
-        let source_dir = unpack(&tar_gz, &download_dir)?;
-        build(
-            &source_dir,
-            &install_dir,
-            self.configure_opts
-                .as_ref()
-                .unwrap_or(&"".to_owned())
-                .split_whitespace(),
-        )?;
-        println!(
-            "{:>12} {}",
-            "Installed".green().bold(),
-            install_dir.display().decorized()
-        );
-        Ok(())
+        match previous {
+            Some(previous) => {
+                manifest.variants = previous.variants.clone();
+                manifest.configure_opts = previous.configure_opts.clone();
+            }
+            None => {
+                let variants = self.variants();
+                manifest.configure_opts = self.configure_opts_for(&variants, install_version)?;
+                manifest.variants = variants;
+            }
+        }
+        manifest.verification = verification;
+        manifest.toolchain = toolchain;
+        preflight(config, &manifest, needs_buildconf(&source_dir))?;
+        install(&progress, &source_dir, config, manifest)
+    }
+    /// Compilers and flags of `--profile`, overridden by `--cc`, `--cxx`, `--cflags` and `--ldflags`
+    fn toolchain(&self, config: &Config) -> Result<manifest::Toolchain, Error> {
+        let profile = match &self.profile {
+            Some(name) => config
+                .profile(name)
+                .cloned()
+                .ok_or_else(|| Error::UnknownProfile(name.clone()))?,
+            None => Default::default(),
+        };
+        Ok(manifest::Toolchain {
+            profile: self.profile.clone(),
+            cc: self.cc.clone().or(profile.cc),
+            cxx: self.cxx.clone().or(profile.cxx),
+            cflags: self.cflags.clone().or(profile.cflags),
+            ldflags: self.ldflags.clone().or(profile.ldflags),
+            ccache: false,
+        })
+    }
+    /// Options translated from the variants, followed by `--configure-opts`
+    fn configure_opts_for(
+        &self,
+        variants: &[Variant],
+        version: Version,
+    ) -> Result<Vec<String>, Error> {
+        Ok(variant::configure_opts(variants, version)?
+            .into_iter()
+            .chain(
+                self.configure_opts
+                    .iter()
+                    .flat_map(|opts| opts.split_whitespace())
+                    .map(ToOwned::to_owned),
+            )
+            .collect())
+    }
+    fn signature_check<'a>(&self, config: &Config, keyring: &'a Path) -> SignatureCheck<'a> {
+        SignatureCheck {
+            policy: self
+                .verify_signature
+                .unwrap_or_else(|| config.verify_signature()),
+            keyring,
+        }
+    }
+    fn checksum_policy(&self, config: &Config) -> ChecksumPolicy {
+        self.checksum_policy
+            .unwrap_or_else(|| config.checksum_policy())
+    }
+    fn versions(&self) -> Vec<Version> {
+        self.targets
+            .iter()
+            .filter_map(|target| match target {
+                Target::Version(version) => Some(*version),
+                Target::Variant(_) => None,
+            })
+            .collect()
+    }
+    fn variants(&self) -> Vec<Variant> {
+        self.targets
+            .iter()
+            .filter_map(|target| match target {
+                Target::Variant(variant) => Some(variant.clone()),
+                Target::Version(_) => None,
+            })
+            .collect()
     }
-}
-
-impl Install {
     fn get_version_from_version_file(&self) -> Result<Version, Error> {
         let version_info = self.version_file.get_version_info()?;
         if let Some(version) = version_info.version.as_version() {
@@ -138,104 +594,1139 @@ impl Install {
     }
 }
 
-fn download(url: &str, dir: impl AsRef<Path>) -> Result<PathBuf, Error> {
-    let curl::Header { content_length } = curl::get_header(url)?;
-    let progress_bar = ProgressBar::new(content_length.unwrap() as u64)
-        .with_style(PROGRESS_STYLE.clone())
-        .with_prefix("Downloading")
-        .with_message(url.to_owned());
+/// Builds the release, git ref or local source of an install again with its recorded options
+pub(super) fn rebuild(config: &Config, previous: &Manifest) -> Result<(), Error> {
+    let local_path = previous
+        .source_url
+        .as_deref()
+        .filter(|url| !url.contains("://"))
+        .map(PathBuf::from);
+    if let Some(path) = local_path.as_ref().filter(|path| !path.exists()) {
+        return Err(Error::SourceNotFound(path.clone()));
+    }
+    let verification = previous.verification.as_ref();
+    let install = Install {
+        checksum: verification
+            .filter(|verification| verification.user_supplied_checksum)
+            .and_then(|verification| verification.checksum.clone()),
+        from_tarball: local_path.clone().filter(|path| path.is_file()),
+        from_source: local_path.filter(|path| path.is_dir()),
+        // accepted when it was installed
+        allow_unverified: true,
+        git: previous.git.as_ref().map(|git| git.repository.clone()),
+        git_ref: previous.git.as_ref().map(|git| git.reference.clone()),
+        label: previous.label.clone(),
+        ..Default::default()
+    };
+    if let Some(local_source) = install.local_source() {
+        return install.install_local(config, local_source, Some(previous));
+    }
 
-    let (stdout, mut stderr) = curl::get_as_reader(url)?;
-    let mut progress_reader = ProgressReader::new(stdout, &progress_bar);
+    let http = config.http();
+    let release = config
+        .release_source(&*http)
+        .fetch_latest(previous.version)?;
+    let mut manifest = Manifest::new(release.version.unwrap());
+    manifest.variants = previous.variants.clone();
+    manifest.configure_opts = previous.configure_opts.clone();
+    manifest.toolchain = previous.toolchain.clone();
+    install.install_releases(config, vec![(release, manifest)])
+}
 
-    let download_file_path = dir.as_ref().join(url.rsplit('/').next().unwrap());
-    let download_file = fs::File::create(&download_file_path)?;
-    let mut file_writer = BufWriter::new(&download_file);
+/// Prints a line above the progress bars
+fn log(progress: &MultiProgress, line: impl Display) {
+    progress.suspend(|| println!("{}", line));
+}
 
-    std::io::copy(&mut progress_reader, &mut file_writer)?;
+/// Source archive ready to be unpacked
+struct Fetched {
+    path: PathBuf,
+    /// Mirror URL the archive was downloaded from, or the first one if it was cached
+    url: String,
+    signer: Option<signature::Signer>,
+}
 
-    if download_file.metadata()?.len() > 0 {
-        progress_bar.finish_and_clear();
-        println!("{:>12} {}", "Downloaded".green().bold(), url);
-        Ok(download_file_path)
+/// Uses the cached archive if it passes the checksum verification, otherwise downloads it into the cache
+fn fetch_source(
+    http: &dyn Transport,
+    progress: &MultiProgress,
+    source: &release::SourceUrls,
+    dir: impl AsRef<Path>,
+    checksum: Option<&Hash>,
+    cache: &DownloadCache,
+    signature_check: SignatureCheck,
+) -> Result<Fetched, Error> {
+    let (filename, urls) = (source.filename, source.urls.as_slice());
+    let filepath = match checksum {
+        Some(checksum) => {
+            let cached = cache.get(filename, checksum).zip(urls.first());
+            if let Some((cached, url)) = cached {
+                log(
+                    progress,
+                    format!("{:>12} {}", "Cached".green().bold(), cached.display()),
+                );
+                let verified = fetch_signature(http, progress, urls, &cached, signature_check)
+                    .and_then(|_| verify(progress, &cached, Some(checksum), signature_check));
+                match verified {
+                    Ok(signer) => {
+                        return Ok(Fetched {
+                            path: cached,
+                            url: url.clone(),
+                            signer,
+                        })
+                    }
+                    Err(e) => {
+                        log(
+                            progress,
+                            format!("{}: {}; Downloading again", "warning".yellow().bold(), e),
+                        );
+                        fs::remove_file(&cached)?;
+                        fs::remove_file(signature::signature_path(&cached)).ok();
+                    }
+                }
+            }
+            cache
+                .download_path(filename, checksum)?
+                .unwrap_or_else(|| dir.as_ref().join(filename))
+        }
+        None => dir.as_ref().join(filename),
+    };
+    let (url, signer) =
+        download_from_mirrors(http, progress, source, &filepath, checksum, signature_check)?;
+    Ok(Fetched {
+        path: filepath,
+        url,
+        signer,
+    })
+}
+
+/// Tries each mirror in order until a download passes the checksum verification,
+/// and returns the URL of the mirror
+fn download_from_mirrors(
+    http: &dyn Transport,
+    progress: &MultiProgress,
+    source: &release::SourceUrls,
+    filepath: impl AsRef<Path>,
+    checksum: Option<&Hash>,
+    signature_check: SignatureCheck,
+) -> Result<(String, Option<signature::Signer>), Error> {
+    let signature_path = signature::signature_path(&filepath);
+    let mut urls = source.urls.iter().peekable();
+    while let Some(url) = urls.next() {
+        // the signature is taken from the same mirror as the archive
+        fs::remove_file(&signature_path).ok();
+        let result = download(http, progress, url, &filepath).and_then(|_| {
+            fetch_signature(
+                http,
+                progress,
+                std::slice::from_ref(url),
+                &filepath,
+                signature_check,
+            )
+            .and_then(|_| verify(progress, &filepath, checksum, signature_check))
+            .inspect_err(|_| {
+                fs::remove_file(&filepath).ok();
+                fs::remove_file(&signature_path).ok();
+            })
+        });
+        match result {
+            Err(e) if urls.peek().is_some() => {
+                log(
+                    progress,
+                    format!(
+                        "{}: {}; Trying the next mirror",
+                        "warning".yellow().bold(),
+                        e
+                    ),
+                );
+            }
+            result => return result.map(|signer| (url.clone(), signer)),
+        }
+    }
+    Err(release::FetchError::NoMirror {
+        filename: source.filename.to_owned(),
+    }
+    .into())
+}
+
+fn download(
+    http: &dyn Transport,
+    progress: &MultiProgress,
+    url: &str,
+    filepath: impl AsRef<Path>,
+) -> Result<(), Error> {
+    if let Some(path) = url.strip_prefix("file://") {
+        fs::copy(path, filepath)?;
+        log(
+            progress,
+            format!("{:>12} {}", "Copied".green().bold(), path),
+        );
+        return Ok(());
+    }
+    let progress_bar = progress.add(
+        ProgressBar::new(0)
+            .with_style(PROGRESS_STYLE.clone())
+            .with_prefix("Downloading")
+            .with_message(url.to_owned()),
+    );
+    download::download(
+        http,
+        url,
+        filepath,
+        &progress_bar,
+        &download::Retry::default(),
+    )?;
+    progress_bar.finish_and_clear();
+    progress.remove(&progress_bar);
+    log(
+        progress,
+        format!("{:>12} {}", "Downloaded".green().bold(), url),
+    );
+    Ok(())
+}
+
+/// Downloads the detached signature `<url>.asc` next to `filepath` unless the policy skips it
+fn fetch_signature(
+    http: &dyn Transport,
+    progress: &MultiProgress,
+    urls: &[String],
+    filepath: impl AsRef<Path>,
+    signature_check: SignatureCheck,
+) -> Result<(), Error> {
+    let signature_path = signature::signature_path(&filepath);
+    match signature_check.policy {
+        signature::Policy::Off => return Ok(()),
+        signature::Policy::Optional if !signature_check.keyring.is_file() => return Ok(()),
+        signature::Policy::Required if !signature_check.keyring.is_file() => {
+            return Err(signature::Error::NoKeyring(signature_check.keyring.to_owned()).into())
+        }
+        _ if signature_path.is_file() => return Ok(()),
+        _ => {}
+    }
+    for url in urls {
+        match download(http, progress, &format!("{}.asc", url), &signature_path) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                fs::remove_file(&signature_path).ok();
+                log(progress, format!("{}: {}", "warning".yellow().bold(), e));
+            }
+        }
+    }
+    if signature_check.policy == signature::Policy::Required {
+        Err(signature::Error::NoSignature(filepath.as_ref().to_owned()).into())
     } else {
-        let mut err_msg = String::new();
-        stderr.read_to_string(&mut err_msg)?;
-        Err(Error::FailedDownload(curl::Error::ExitFailed(
-            "curl".to_owned(),
-            err_msg,
-        )))
+        Ok(())
     }
 }
 
-fn verify(filepath: impl AsRef<Path>, checksum: Option<&Hash>) -> Result<(), Error> {
+fn verify(
+    progress: &MultiProgress,
+    filepath: impl AsRef<Path>,
+    checksum: Option<&Hash>,
+    signature_check: SignatureCheck,
+) -> Result<Option<signature::Signer>, Error> {
+    let filename = filepath.as_ref().file_name().unwrap().to_string_lossy();
     if let Some(checksum) = checksum {
         let hash_type = checksum.hash_type();
-        let file = fs::File::open(filepath)?;
-        let progress_bar = ProgressBar::new(file.metadata()?.len())
-            .with_style(PROGRESS_STYLE.clone())
-            .with_prefix("Verifying")
-            .with_message(hash_type);
+        let file = fs::File::open(&filepath)?;
+        let progress_bar = progress.add(
+            ProgressBar::new(file.metadata()?.len())
+                .with_style(PROGRESS_STYLE.clone())
+                .with_prefix("Verifying")
+                .with_message(format!("{} {}", filename, hash_type)),
+        );
 
         checksum.verify(ProgressReader::new(file, &progress_bar))?;
         progress_bar.finish_and_clear();
-        println!("{:>12} {} checksum", "Verified".green().bold(), hash_type);
-    } else {
-        println!(
-            "{:>12} {}: No checksum",
-            "Verifying".cyan().bold(),
-            "warning".yellow().bold()
-        );
+        progress.remove(&progress_bar);
     }
-    Ok(())
+
+    let signer = match signature_check.policy {
+        signature::Policy::Off => None,
+        // no keyring is bundled, so say why the signature isn't verified
+        signature::Policy::Optional if !signature_check.keyring.is_file() => {
+            log(
+                progress,
+                format!(
+                    "{:>12} signature of {} not verified: no keyring at {}",
+                    "Skipped".yellow().bold(),
+                    filename,
+                    signature_check.keyring.display()
+                ),
+            );
+            None
+        }
+        signature::Policy::Optional if !signature::signature_path(&filepath).is_file() => {
+            log(
+                progress,
+                format!(
+                    "{:>12} signature of {} not verified: no signature is found",
+                    "Skipped".yellow().bold(),
+                    filename
+                ),
+            );
+            None
+        }
+        _ => Some(signature::verify(signature_check.keyring, &filepath)?),
+    };
+
+    let line = match (checksum, &signer) {
+        (Some(checksum), Some(signer)) => format!(
+            "{:>12} {} checksum and signature of {} by {}",
+            "Verified".green().bold(),
+            checksum.hash_type(),
+            filename,
+            signer
+        ),
+        (Some(checksum), None) => format!(
+            "{:>12} {} checksum of {}",
+            "Verified".green().bold(),
+            checksum.hash_type(),
+            filename
+        ),
+        (None, Some(signer)) => format!(
+            "{:>12} signature of {} by {}",
+            "Verified".green().bold(),
+            filename,
+            signer
+        ),
+        (None, None) => format!(
+            "{:>12} {}: No checksum of {}",
+            "Verifying".cyan().bold(),
+            "warning".yellow().bold(),
+            filename
+        ),
+    };
+    log(progress, line);
+    Ok(signer)
 }
 
-fn unpack(tar_gz: impl AsRef<Path>, dst_dir: impl AsRef<Path>) -> Result<PathBuf, Error> {
-    let file = fs::File::open(&tar_gz)?;
-    let progress_bar = ProgressBar::new(file.metadata()?.len())
-        .with_style(PROGRESS_STYLE.clone())
-        .with_prefix("Unpacking")
-        .with_message(tar_gz.as_ref().to_str().unwrap().to_owned());
+fn unpack(
+    progress: &MultiProgress,
+    tarball: impl AsRef<Path>,
+    dst_dir: impl AsRef<Path>,
+) -> Result<PathBuf, Error> {
+    let tarball = tarball.as_ref();
+    let format = tarball
+        .file_name()
+        .and_then(|filename| archive::Format::from_filename(&filename.to_string_lossy()))
+        .ok_or_else(|| Error::UnknownArchiveFormat(tarball.to_owned()))?;
+    let file = fs::File::open(tarball)?;
+    let progress_bar = progress.add(
+        ProgressBar::new(file.metadata()?.len())
+            .with_style(PROGRESS_STYLE.clone())
+            .with_prefix("Unpacking")
+            .with_message(tarball.display().to_string()),
+    );
 
     let file_reader = BufReader::new(file);
     let progress_reader = ProgressReader::new(file_reader, &progress_bar);
-    let gz_decoder = GzDecoder::new(progress_reader);
-    let mut tar_archive = Archive::new(gz_decoder);
-
-    tar_archive.unpack(&dst_dir)?;
+    let source_dir = archive::unpack(progress_reader, format, &dst_dir)?;
     progress_bar.finish_and_clear();
+    progress.remove(&progress_bar);
+
+    log(
+        progress,
+        format!("{:>12} {}", "Unpacked".green().bold(), tarball.display()),
+    );
+    Ok(source_dir)
+}
 
-    println!(
-        "{:>12} {}",
-        "Unpacked".green().bold(),
-        tar_gz.as_ref().display()
+/// Fetches the ref of the repository, whose local path is recorded as absolute
+fn checkout(
+    progress: &MultiProgress,
+    repository: &str,
+    reference: Option<&str>,
+    dst_dir: impl AsRef<Path>,
+) -> Result<manifest::GitSource, Error> {
+    let repository = match Path::new(repository).canonicalize() {
+        Ok(path) => path.to_string_lossy().into_owned(),
+        Err(_) => repository.to_owned(),
+    };
+    log(
+        progress,
+        format!(
+            "{:>12} {} {}",
+            "Fetching".cyan().bold(),
+            repository,
+            reference.unwrap_or("(default branch)")
+        ),
+    );
+    let checkout = git::checkout(&repository, reference, dst_dir)?;
+    log(
+        progress,
+        format!(
+            "{:>12} {} at {}",
+            "Checked out".green().bold(),
+            checkout.reference,
+            checkout.commit
+        ),
     );
-    let tar_gz_filename = tar_gz.as_ref().file_name().unwrap().to_str().unwrap();
-    let unpaked_dirname = &tar_gz_filename[..tar_gz_filename.len() - ".tar.gz".len()];
-    Ok(dst_dir.as_ref().join(unpaked_dirname))
+    Ok(manifest::GitSource {
+        repository,
+        reference: checkout.reference,
+        commit: checkout.commit,
+    })
+}
+
+/// `X.Y-dev` for a `PHP-X.Y` branch, otherwise `<ref>-<short commit>`
+fn default_label(git_source: &manifest::GitSource) -> String {
+    let reference = &git_source.reference;
+    match reference.strip_prefix("PHP-") {
+        Some(minor) if minor.split('.').count() == 2 && minor.parse::<Version>().is_ok() => {
+            format!("{}-dev", minor)
+        }
+        _ => {
+            let reference = reference.replace(
+                |c: char| !(c.is_ascii_alphanumeric() || "._+".contains(c)),
+                "-",
+            );
+            let short_commit = &git_source.commit[..git_source.commit.len().min(7)];
+            format!("{}-{}", reference.trim_matches('-'), short_commit)
+        }
+    }
+}
+
+/// Reads `PHP_VERSION` defined in `main/php_version.h` of the source
+fn source_version(source_dir: impl AsRef<Path>) -> Result<Version, Error> {
+    let unknown = || Error::UnknownSourceVersion(source_dir.as_ref().to_owned());
+    let header = fs::read_to_string(source_dir.as_ref().join("main").join("php_version.h"))
+        .map_err(|_| unknown())?;
+    header
+        .lines()
+        .find_map(|line| {
+            line.trim()
+                .strip_prefix("#define PHP_VERSION ")?
+                .trim()
+                .strip_prefix('"')?
+                .strip_suffix('"')?
+                // e.g. `8.4.0-dev` of a git checkout
+                .split('-')
+                .next()?
+                .parse()
+                .ok()
+        })
+        .ok_or_else(unknown)
+}
+
+/// Builds the source into the versions directory, and writes the manifest there
+fn install(
+    progress: &MultiProgress,
+    source_dir: impl AsRef<Path>,
+    config: &Config,
+    mut manifest: Manifest,
+) -> Result<(), Error> {
+    let install_dir = config.versions_dir().join(manifest.install_name());
+    let build_log = make::Log::create(config.logs_dir().join(manifest.install_name()).join(
+        format!("{}.log", chrono::Local::now().format("%Y%m%d-%H%M%S")),
+    ))?;
+    if let Some((built, duration)) = estimated_build_time(config, manifest.version) {
+        log(
+            progress,
+            format!(
+                "{:>12} about {} from the build of {}",
+                "Estimated".cyan().bold(),
+                HumanDuration(duration),
+                built.decorized_with_prefix()
+            ),
+        );
+    }
+    manifest.toolchain.ccache = config.ccache() && {
+        let found = which::which("ccache").is_ok();
+        if !found {
+            log(
+                progress,
+                format!(
+                    "{}: ccache isn't found; building without it",
+                    "warning".yellow().bold()
+                ),
+            );
+        }
+        found
+    };
+    let started = Instant::now();
+    build(
+        progress,
+        &source_dir,
+        &install_dir,
+        manifest.configure_opts.iter().map(String::as_str),
+        &build_log,
+        &make::Options {
+            jobs: config.make_jobs().unwrap_or_else(jobs::default_jobs),
+            nice: config.nice(),
+            envs: manifest.toolchain.envs(),
+        },
+    )?;
+    let duration = started.elapsed();
+    manifest.build_seconds = Some(duration.as_secs());
+    manifest.built_at = Some(chrono::Utc::now());
+    manifest.compiler = compiler_version(manifest.toolchain.cc.as_deref());
+    manifest.write(&install_dir)?;
+
+    log(
+        progress,
+        format!(
+            "{:>12} {} in {}",
+            "Installed".green().bold(),
+            install_dir.display().decorized(),
+            HumanDuration(duration)
+        ),
+    );
+    Ok(())
+}
+
+/// Build time of the latest install of the same minor version having it recorded
+fn estimated_build_time(config: &Config, version: Version) -> Option<(Version, Duration)> {
+    version::installed(config)
+        .filter(|installed| installed.is_same_minor(version))
+        .sorted()
+        .rev()
+        .find_map(|installed| {
+            let install_dir = config.versions_dir().join(installed.to_string());
+            let build_seconds = Manifest::read(install_dir).ok()??.build_seconds?;
+            Some((installed, Duration::from_secs(build_seconds)))
+        })
+}
+
+/// First line of `<cc> --version`, where `cc` is `--cc`, `$CC` or `cc` as `configure` uses
+fn compiler_version(cc: Option<&str>) -> Option<String> {
+    let cc = cc
+        .map(str::to_owned)
+        .or_else(|| std::env::var("CC").ok())
+        .unwrap_or_else(|| "cc".to_owned());
+    let mut cc = cc.split_whitespace();
+    let output = std::process::Command::new(cc.next()?)
+        .args(cc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_owned())
+}
+
+/// Checks the build dependencies of the manifest with its compiler, unless `--skip-preflight`
+fn preflight(config: &Config, manifest: &Manifest, buildconf: bool) -> Result<(), Error> {
+    if config.skip_preflight() {
+        return Ok(());
+    }
+    let configure_opts = manifest
+        .configure_opts
+        .iter()
+        .map(String::as_str)
+        .collect_vec();
+    let missing = deps::Missing::check(
+        deps::requirements(manifest.version, &configure_opts, buildconf),
+        manifest.toolchain.cc.as_deref(),
+    );
+    if !missing.is_empty() {
+        return Err(Error::MissingBuildDeps(missing));
+    }
+    Ok(())
+}
+
+/// A git checkout has no `configure` until it's generated
+fn needs_buildconf(source_dir: impl AsRef<Path>) -> bool {
+    let source_dir = source_dir.as_ref();
+    !source_dir.join("configure").exists() && source_dir.join("buildconf").exists()
 }
 
 #[cfg(unix)]
 fn build<'a>(
+    progress: &MultiProgress,
     src_dir: impl AsRef<Path>,
     dst_dir: impl AsRef<Path>,
     configure_opts: impl Iterator<Item = &'a str>,
+    build_log: &make::Log,
+    options: &make::Options,
 ) -> Result<(), Error> {
     use make::Command;
 
-    println!(
-        "{:>12} {}",
-        "Building".cyan().bold(),
-        src_dir.as_ref().display()
+    log(
+        progress,
+        format!(
+            "{:>12} {}",
+            "Building".cyan().bold(),
+            src_dir.as_ref().display()
+        ),
     );
     let current_dir = src_dir.as_ref();
+    let buildconf = needs_buildconf(current_dir);
+    let steps = if buildconf { 4 } else { 3 };
+    let step = |n: usize| (n + steps - 3, steps);
+    let configure_opts = configure_opts.collect_vec();
 
+    if buildconf {
+        make::Buildconf {}.run(current_dir, (1, steps), build_log, options, progress)?;
+    }
     make::Configure {
         prefix: dst_dir.as_ref(),
-        opts: configure_opts.collect(),
+        opts: configure_opts,
     }
-    .run(current_dir)?;
-    make::Make {}.run(current_dir)?;
-    make::Install {}.run(current_dir)?;
+    .run(current_dir, step(1), build_log, options, progress)?;
+    make::Make { jobs: options.jobs }.run(current_dir, step(2), build_log, options, progress)?;
+    make::Install {}.run(current_dir, step(3), build_log, options, progress)?;
     Ok(())
 }
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::cli::{Cli, SubCommand};
+    use sha2::Digest;
+
+    fn parse(args: &[&str]) -> Install {
+        match Cli::try_parse_args(args).map(|cli| cli.subcmd) {
+            Ok(SubCommand::Install(install)) => *install,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    /// `configure` of a fake PHP, which writes a Makefile installing `bin/php`
+    const FAKE_CONFIGURE: &str = "#!/bin/sh\nprefix=\"${1#--prefix=}\"\nprintf 'all:\\n\\ninstall:\\n\\tmkdir -p %s/bin\\n\\tcp php %s/bin/php\\n' \"$prefix\" \"$prefix\" > Makefile\n";
+
+    /// Source archive of a fake PHP
+    pub(in crate::commands) fn fake_source(dir: &Path, version: &str) {
+        let configure = FAKE_CONFIGURE;
+        let php = format!("#!/bin/sh\necho 'PHP {}'\n", version);
+
+        let filepath = dir.join(format!("php-{}.tar.gz", version));
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&filepath).unwrap(),
+            flate2::Compression::fast(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        let php_version_h = format!("#define PHP_VERSION \"{}\"\n", version);
+        for (name, content) in [
+            ("configure", configure),
+            ("php", php.as_str()),
+            ("main/php_version.h", php_version_h.as_str()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(
+                    &mut header,
+                    format!("php-{}/{}", version, name),
+                    content.as_bytes(),
+                )
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let checksum = sha2::Sha256::digest(fs::read(&filepath).unwrap());
+        fs::write(
+            dir.join(format!("php-{}.tar.gz.sha256", version)),
+            format!("{:x}", checksum),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn install_from_directory() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.9");
+        fake_source(sources.path(), "8.2.10");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+
+        parse(&["phpup", "install", "8.2"]).run(&config).unwrap();
+
+        let install_dir = config.versions_dir().join("8.2.10");
+        assert!(install_dir.join("bin").join("php").is_file());
+        let manifest = Manifest::read(&install_dir).unwrap().unwrap();
+        assert_eq!(manifest.version, "8.2.10".parse().unwrap());
+        let archive = sources
+            .path()
+            .canonicalize()
+            .unwrap()
+            .join("php-8.2.10.tar.gz");
+        assert_eq!(
+            manifest.source_url,
+            Some(format!("file://{}", archive.display()))
+        );
+        assert!(manifest.built_at.is_some());
+        assert!(manifest.build_seconds.is_some());
+        assert_eq!(
+            manifest.phpup_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(DownloadCache::new(config.cache_dir()).entries().count(), 1);
+    }
+
+    #[test]
+    fn install_several_versions() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        for version in ["7.4.33", "8.1.23", "8.2.10"] {
+            fake_source(sources.path(), version);
+        }
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+
+        parse(&["phpup", "install", "7.4", "8.1", "8.2", "8.2.10", "-cgi"])
+            .run(&config)
+            .unwrap();
+
+        for version in ["7.4.33", "8.1.23", "8.2.10"] {
+            let install_dir = config.versions_dir().join(version);
+            assert!(install_dir.join("bin").join("php").is_file());
+            let manifest = Manifest::read(&install_dir).unwrap().unwrap();
+            assert_eq!(manifest.configure_opts, vec!["--disable-cgi"]);
+        }
+        assert_eq!(DownloadCache::new(config.cache_dir()).entries().count(), 3);
+    }
+
+    #[test]
+    fn install_with_profile() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.10");
+        fs::write(
+            base_dir.path().join("config.toml"),
+            "[profiles.clang]\ncc = \"clang\"\ncflags = \"-O2\"\n",
+        )
+        .unwrap();
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight()
+            .load_file()
+            .unwrap();
+
+        assert!(matches!(
+            parse(&["phpup", "install", "--profile", "gcc", "8.2"]).run(&config),
+            Err(Error::UnknownProfile(profile)) if profile == "gcc"
+        ));
+        parse(&[
+            "phpup",
+            "install",
+            "--profile",
+            "clang",
+            "--cflags",
+            "-O0 -g",
+            "--ldflags=-fuse-ld=lld",
+            "8.2",
+        ])
+        .run(&config)
+        .unwrap();
+
+        let manifest = Manifest::read(config.versions_dir().join("8.2.10"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            manifest.toolchain,
+            manifest::Toolchain {
+                profile: Some("clang".to_owned()),
+                cc: Some("clang".to_owned()),
+                cxx: None,
+                cflags: Some("-O0 -g".to_owned()),
+                ldflags: Some("-fuse-ld=lld".to_owned()),
+                ccache: false,
+            }
+        );
+    }
+
+    #[test]
+    fn preflight_before_fetching() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "7.3.33");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap());
+        let missing_cc = |args: &[&str]| match parse(args).run(&config) {
+            Err(Error::MissingBuildDeps(missing)) => missing
+                .dependencies
+                .iter()
+                .any(|dependency| dependency.name == "cc"),
+            other => panic!("{:?}", other),
+        };
+
+        assert!(missing_cc(&[
+            "phpup",
+            "install",
+            "--cc",
+            "/nonexistent/cc",
+            "7.3"
+        ]));
+        assert_eq!(fs::read_dir(config.cache_dir()).unwrap().count(), 0);
+        assert!(missing_cc(&[
+            "phpup",
+            "install",
+            "--cc",
+            "/nonexistent/cc",
+            "--git",
+            "/nonexistent/php-src"
+        ]));
+    }
+
+    #[test]
+    fn require_signature() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.10");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+
+        let result =
+            parse(&["phpup", "install", "--verify-signature=required", "8.2"]).run(&config);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidSignature(signature::Error::NoKeyring(_)))
+        ));
+        assert!(!config.versions_dir().join("8.2.10").exists());
+
+        // a keyring without the signature
+        fs::write(config.keyring(), b"").unwrap();
+        let result =
+            parse(&["phpup", "install", "--verify-signature=required", "8.2"]).run(&config);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidSignature(signature::Error::NoSignature(_)))
+        ));
+
+        parse(&["phpup", "install", "--verify-signature=off", "8.2"])
+            .run(&config)
+            .unwrap();
+        assert!(config.versions_dir().join("8.2.10").exists());
+    }
+
+    #[test]
+    fn checksum_policy() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.10");
+        let archive = sources.path().join("php-8.2.10.tar.gz");
+        let checksum = sha2::Sha256::digest(fs::read(&archive).unwrap());
+        fs::remove_file(sources.path().join("php-8.2.10.tar.gz.sha256")).unwrap();
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+
+        let result = parse(&["phpup", "install", "--checksum-policy=strict", "8.2"]).run(&config);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidChecksum(
+                release::ChecksumError::NoChecksum { .. }
+            ))
+        ));
+        assert!(!config.versions_dir().join("8.2.10").exists());
+
+        let user_checksum = format!("sha256:{:x}", checksum);
+        parse(&[
+            "phpup",
+            "install",
+            "--checksum-policy=sha256-only",
+            "--checksum",
+            &user_checksum,
+            "8.2",
+        ])
+        .run(&config)
+        .unwrap();
+        let install_dir = config.versions_dir().join("8.2.10");
+        let verification = Manifest::read(&install_dir)
+            .unwrap()
+            .unwrap()
+            .verification
+            .unwrap();
+        assert_eq!(verification.checksum_policy, ChecksumPolicy::Sha256Only);
+        assert_eq!(verification.checksum, Some(user_checksum.parse().unwrap()));
+        assert!(verification.user_supplied_checksum);
+
+        let result = parse(&[
+            "phpup",
+            "install",
+            "--checksum",
+            &user_checksum,
+            "8.1",
+            "8.2",
+        ])
+        .run(&config);
+        assert!(matches!(result, Err(Error::ChecksumForSeveralVersions)));
+    }
+
+    #[test]
+    fn install_from_local_source() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.10");
+        fake_source(sources.path(), "8.1.23");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_skip_preflight();
+
+        let tarball = sources.path().join("php-8.2.10.tar.gz");
+        parse(&[
+            "phpup",
+            "install",
+            "--from-tarball",
+            tarball.to_str().unwrap(),
+            "-cgi",
+        ])
+        .run(&config)
+        .unwrap();
+        let manifest = Manifest::read(config.versions_dir().join("8.2.10"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(manifest.configure_opts, vec!["--disable-cgi"]);
+        assert!(manifest.verification.is_some());
+
+        // e.g. a patched source tree
+        let tarball = fs::File::open(sources.path().join("php-8.1.23.tar.gz")).unwrap();
+        let source_dir = archive::unpack(tarball, archive::Format::Gz, sources.path()).unwrap();
+        parse(&[
+            "phpup",
+            "install",
+            "--from-source",
+            source_dir.to_str().unwrap(),
+        ])
+        .run(&config)
+        .unwrap();
+        assert!(config
+            .versions_dir()
+            .join("8.1.23")
+            .join("bin")
+            .join("php")
+            .is_file());
+
+        let result = parse(&[
+            "phpup",
+            "install",
+            "--from-source",
+            sources.path().to_str().unwrap(),
+        ])
+        .run(&config);
+        assert!(matches!(result, Err(Error::UnknownSourceVersion(_))));
+        let result = parse(&[
+            "phpup",
+            "install",
+            "--from-source",
+            source_dir.to_str().unwrap(),
+            "8.1",
+        ])
+        .run(&config);
+        assert!(matches!(result, Err(Error::VersionWithLocalSource)));
+    }
+
+    #[test]
+    fn source_directory_under_strict_policies() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.1.23");
+        let tarball = fs::File::open(sources.path().join("php-8.1.23.tar.gz")).unwrap();
+        let source_dir = archive::unpack(tarball, archive::Format::Gz, sources.path()).unwrap();
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_skip_preflight();
+
+        for policy in ["--checksum-policy=strict", "--verify-signature=required"] {
+            let result = parse(&[
+                "phpup",
+                "install",
+                policy,
+                "--from-source",
+                source_dir.to_str().unwrap(),
+            ])
+            .run(&config);
+            assert!(matches!(result, Err(Error::UnverifiedSource(_))));
+        }
+        parse(&[
+            "phpup",
+            "install",
+            "--checksum-policy=strict",
+            "--from-source",
+            source_dir.to_str().unwrap(),
+            "--allow-unverified",
+        ])
+        .run(&config)
+        .unwrap();
+        let verification = Manifest::read(config.versions_dir().join("8.1.23"))
+            .unwrap()
+            .unwrap()
+            .verification
+            .unwrap();
+        assert_eq!(verification.checksum_policy, ChecksumPolicy::Strict);
+        assert_eq!(verification.checksum, None);
+    }
+
+    /// Repository of a fake php-src with `PHP-8.4` and `master` (default) branches
+    fn fake_repository(dir: &Path) {
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args([
+                    "-c",
+                    "user.name=phpup",
+                    "-c",
+                    "user.email=phpup@example.com",
+                ])
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        let buildconf = format!(
+            "#!/bin/sh\ncat > configure <<'EOF'\n{}EOF\nchmod +x configure\n",
+            FAKE_CONFIGURE
+        );
+        fs::create_dir(dir.join("main")).unwrap();
+        git(&["init", "--quiet"]);
+        git(&["checkout", "--quiet", "-b", "PHP-8.4"]);
+        for (branch, version) in [("PHP-8.4", "8.4.1-dev"), ("master", "8.5.0-dev")] {
+            if branch == "master" {
+                git(&["checkout", "--quiet", "-b", "master"]);
+            }
+            fs::write(
+                dir.join("main").join("php_version.h"),
+                format!("#define PHP_VERSION \"{}\"\n", version),
+            )
+            .unwrap();
+            for (name, content) in [
+                ("buildconf", buildconf.clone()),
+                ("php", format!("#!/bin/sh\necho 'PHP {}'\n", version)),
+            ] {
+                fs::write(dir.join(name), content).unwrap();
+                let mut permissions = fs::metadata(dir.join(name)).unwrap().permissions();
+                std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, 0o755);
+                fs::set_permissions(dir.join(name), permissions).unwrap();
+            }
+            git(&["add", "."]);
+            git(&["commit", "--quiet", "-m", version]);
+        }
+    }
+
+    #[test]
+    fn install_from_git() {
+        if which::which("git").is_err() {
+            return;
+        }
+        let base_dir = tempfile::tempdir().unwrap();
+        let repository = tempfile::tempdir().unwrap();
+        fake_repository(repository.path());
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_skip_preflight();
+        let repository_path = repository.path().to_str().unwrap();
+
+        parse(&[
+            "phpup",
+            "install",
+            "--git",
+            repository_path,
+            "--ref",
+            "PHP-8.4",
+        ])
+        .run(&config)
+        .unwrap();
+        let install_dir = config.versions_dir().join("8.4-dev");
+        assert!(install_dir.join("bin").join("php").is_file());
+        let manifest = Manifest::read(&install_dir).unwrap().unwrap();
+        assert_eq!(manifest.version, "8.4.1".parse().unwrap());
+        assert_eq!(manifest.local(), Local::Labeled("8.4-dev".to_owned()));
+        let git_source = manifest.git.unwrap();
+        assert_eq!(git_source.reference, "PHP-8.4");
+        assert_eq!(
+            Path::new(&git_source.repository),
+            repository.path().canonicalize().unwrap()
+        );
+        assert_eq!(git_source.commit.len(), 40);
+
+        // the default branch
+        parse(&["phpup", "install", "--git", repository_path])
+            .run(&config)
+            .unwrap();
+        let labeled = version::labeled(&config).collect::<Vec<_>>();
+        assert_eq!(labeled.len(), 2);
+        assert!(labeled[1].starts_with("master-"));
+        let manifest = Manifest::read(config.versions_dir().join(&labeled[1]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(manifest.version, "8.5.0".parse().unwrap());
+
+        let result = parse(&[
+            "phpup",
+            "install",
+            "--git",
+            repository_path,
+            "--label",
+            "dev",
+        ])
+        .run(&config);
+        assert!(matches!(result, Err(Error::InvalidLabel(_))));
+        let result = parse(&["phpup", "install", "--label", "8.2-dev", "8.2"]).run(&config);
+        assert!(matches!(result, Err(Error::LabelWithoutLocalSource)));
+    }
+
+    #[test]
+    fn default_labels() {
+        let git_source = |reference: &str| manifest::GitSource {
+            repository: "https://github.com/php/php-src.git".to_owned(),
+            reference: reference.to_owned(),
+            commit: "1a2b3c4d5e6f7a8b9c0d1a2b3c4d5e6f7a8b9c0d".to_owned(),
+        };
+        assert_eq!(default_label(&git_source("PHP-8.4")), "8.4-dev");
+        assert_eq!(default_label(&git_source("master")), "master-1a2b3c4");
+        assert_eq!(default_label(&git_source("PHP-8.4.1")), "PHP-8.4.1-1a2b3c4");
+        assert_eq!(
+            default_label(&git_source("feature/jit")),
+            "feature-jit-1a2b3c4"
+        );
+    }
+
+    #[test]
+    fn parse_variants() {
+        let install = parse(&["phpup", "install", "8.2", "+intl", "8.1", "+gd", "-cgi"]);
+        assert_eq!(
+            install.versions(),
+            vec!["8.2".parse().unwrap(), "8.1".parse().unwrap()]
+        );
+        assert_eq!(
+            install.variants(),
+            vec![
+                "+intl".parse().unwrap(),
+                "+gd".parse().unwrap(),
+                "-cgi".parse().unwrap()
+            ]
+        );
+        assert!(Cli::try_parse_args(["phpup", "install", "8.2", "-cgi"]).is_ok());
+        assert!(Cli::try_parse_args(["phpup", "install", "8.2", "--", "-cgi"]).is_ok());
+
+        // options may follow the versions and variants
+        let install = parse(&[
+            "phpup",
+            "install",
+            "8.1",
+            "-cgi",
+            "--configure-opts",
+            "--with-iconv=/opt/iconv",
+            "+intl",
+            "--offline",
+            "--cflags",
+            "-O2",
+        ]);
+        assert_eq!(install.versions(), vec!["8.1".parse().unwrap()]);
+        assert_eq!(
+            install.variants(),
+            vec!["-cgi".parse().unwrap(), "+intl".parse().unwrap()]
+        );
+        assert_eq!(
+            install.configure_opts.as_deref(),
+            Some("--with-iconv=/opt/iconv")
+        );
+        assert_eq!(install.cflags.as_deref(), Some("-O2"));
+    }
+}
@@ -0,0 +1,391 @@
+//! Tools and libraries needed to build PHP, checked before running `configure`
+
+use crate::version::Version;
+use itertools::Itertools;
+use std::fmt;
+use std::fs;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A command looked up in `PATH`
+    Tool,
+    /// A library looked up by `pkg-config`
+    Module,
+}
+
+/// A build dependency with the packages providing it on each distro
+#[derive(Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: &'static str,
+    pub kind: Kind,
+    /// Packages of apt, dnf, apk and pacman
+    packages: [&'static str; 4],
+}
+
+const DEPENDENCIES: &[Dependency] = &[
+    tool("cc", ["build-essential", "gcc", "build-base", "base-devel"]),
+    tool("make", ["make", "make", "make", "make"]),
+    tool(
+        "pkg-config",
+        ["pkg-config", "pkgconf-pkg-config", "pkgconf", "pkgconf"],
+    ),
+    tool("autoconf", ["autoconf", "autoconf", "autoconf", "autoconf"]),
+    tool("bison", ["bison", "bison", "bison", "bison"]),
+    tool("re2c", ["re2c", "re2c", "re2c", "re2c"]),
+    module(
+        "libxml-2.0",
+        ["libxml2-dev", "libxml2-devel", "libxml2-dev", "libxml2"],
+    ),
+    module(
+        "sqlite3",
+        ["libsqlite3-dev", "sqlite-devel", "sqlite-dev", "sqlite"],
+    ),
+    module(
+        "oniguruma",
+        [
+            "libonig-dev",
+            "oniguruma-devel",
+            "oniguruma-dev",
+            "oniguruma",
+        ],
+    ),
+    module(
+        "libcurl",
+        ["libcurl4-openssl-dev", "libcurl-devel", "curl-dev", "curl"],
+    ),
+    module(
+        "openssl",
+        ["libssl-dev", "openssl-devel", "openssl-dev", "openssl"],
+    ),
+    module("icu-uc", ["libicu-dev", "libicu-devel", "icu-dev", "icu"]),
+    module(
+        "libzip",
+        ["libzip-dev", "libzip-devel", "libzip-dev", "libzip"],
+    ),
+    module(
+        "libpng",
+        ["libpng-dev", "libpng-devel", "libpng-dev", "libpng"],
+    ),
+    module(
+        "libsodium",
+        [
+            "libsodium-dev",
+            "libsodium-devel",
+            "libsodium-dev",
+            "libsodium",
+        ],
+    ),
+    module(
+        "libargon2",
+        ["libargon2-dev", "libargon2-devel", "argon2-dev", "argon2"],
+    ),
+    module(
+        "libffi",
+        ["libffi-dev", "libffi-devel", "libffi-dev", "libffi"],
+    ),
+    module("zlib", ["zlib1g-dev", "zlib-devel", "zlib-dev", "zlib"]),
+    module(
+        "libxslt",
+        ["libxslt1-dev", "libxslt-devel", "libxslt-dev", "libxslt"],
+    ),
+];
+
+/// Extensions linked with a pkg-config module since PHP 7.4:
+/// the name in the configure options, whether it's enabled by default, and the module
+const EXTENSIONS: &[(&str, bool, &str)] = &[
+    ("libxml", true, "libxml-2.0"),
+    ("sqlite3", true, "sqlite3"),
+    ("pdo-sqlite", true, "sqlite3"),
+    ("mbstring", false, "oniguruma"),
+    ("curl", false, "libcurl"),
+    ("openssl", false, "openssl"),
+    ("intl", false, "icu-uc"),
+    ("zip", false, "libzip"),
+    ("gd", false, "libpng"),
+    ("sodium", false, "libsodium"),
+    ("password-argon2", false, "libargon2"),
+    ("ffi", false, "libffi"),
+    ("zlib", false, "zlib"),
+    ("xsl", false, "libxslt"),
+];
+
+const fn tool(name: &'static str, packages: [&'static str; 4]) -> Dependency {
+    Dependency {
+        name,
+        kind: Kind::Tool,
+        packages,
+    }
+}
+
+const fn module(name: &'static str, packages: [&'static str; 4]) -> Dependency {
+    Dependency {
+        name,
+        kind: Kind::Module,
+        packages,
+    }
+}
+
+fn dependency(name: &str) -> &'static Dependency {
+    DEPENDENCIES
+        .iter()
+        .find(|dependency| dependency.name == name)
+        .unwrap()
+}
+
+/// Whether an extension is enabled by the configure options; the last option wins
+fn is_enabled(configure_opts: &[&str], name: &str, default: bool) -> bool {
+    configure_opts
+        .iter()
+        .rev()
+        .find_map(|opt| {
+            let opt = opt.split('=').next().unwrap();
+            let (enabled, rest) = if let Some(rest) = opt.strip_prefix("--enable-") {
+                (true, rest)
+            } else if let Some(rest) = opt.strip_prefix("--with-") {
+                (true, rest)
+            } else if let Some(rest) = opt.strip_prefix("--disable-") {
+                (false, rest)
+            } else {
+                (false, opt.strip_prefix("--without-")?)
+            };
+            (rest == name).then_some(enabled)
+        })
+        .unwrap_or(default && !configure_opts.contains(&"--disable-all"))
+}
+
+/// Commands to build any version;
+/// a git checkout also needs the tools generating `configure` and the parsers
+pub fn tools(from_git: bool) -> Vec<&'static Dependency> {
+    tool_names(from_git).into_iter().map(dependency).collect()
+}
+
+fn tool_names(from_git: bool) -> Vec<&'static str> {
+    let mut names = vec!["cc", "make"];
+    if from_git {
+        names.extend(["autoconf", "bison", "re2c"]);
+    }
+    names
+}
+
+/// Dependencies to build `version` with the configure options, following the `tools`
+pub fn requirements(
+    version: Version,
+    configure_opts: &[&str],
+    from_git: bool,
+) -> Vec<&'static Dependency> {
+    let mut names = tool_names(from_git);
+    if (
+        version.major_version(),
+        version.minor_version().unwrap_or(0),
+    ) >= (7, 4)
+    {
+        names.push("pkg-config");
+        names.extend(
+            EXTENSIONS
+                .iter()
+                .filter(|(name, default, _)| is_enabled(configure_opts, name, *default))
+                .map(|(_, _, module)| *module),
+        );
+    }
+    names.into_iter().unique().map(dependency).collect()
+}
+
+impl Dependency {
+    /// Whether it's installed, where `cc` is the C compiler given to the build
+    pub fn is_found(&self, cc: Option<&str>) -> bool {
+        match self.kind {
+            Kind::Tool => which::which(self.command(cc)).is_ok(),
+            Kind::Module => Command::new("pkg-config")
+                .args(["--exists", self.name])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success()),
+        }
+    }
+    /// `cc` or else `$CC` is the C compiler `configure` uses
+    fn command(&self, cc: Option<&str>) -> String {
+        match self.name {
+            "cc" => cc
+                .map(str::to_owned)
+                .or_else(|| std::env::var("CC").ok())
+                .and_then(|cc| cc.split_whitespace().next().map(str::to_owned))
+                .unwrap_or_else(|| "cc".to_owned()),
+            name => name.to_owned(),
+        }
+    }
+    pub fn package(&self, manager: PackageManager) -> &'static str {
+        self.packages[manager as usize]
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Tool => write!(f, "{}", self.name),
+            Kind::Module => write!(f, "{} (pkg-config)", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    Apk,
+    Pacman,
+}
+
+impl PackageManager {
+    /// Detects the package manager of the distro by `/etc/os-release`
+    pub fn detect() -> Option<Self> {
+        Self::from_os_release(&fs::read_to_string("/etc/os-release").ok()?)
+    }
+    fn from_os_release(os_release: &str) -> Option<Self> {
+        let field = |key: &str| {
+            os_release.lines().find_map(|line| {
+                let value = line.strip_prefix(key)?.strip_prefix('=')?;
+                Some(value.trim_matches('"').to_owned())
+            })
+        };
+        let ids = [field("ID"), field("ID_LIKE")]
+            .into_iter()
+            .flatten()
+            .join(" ");
+        ids.split_whitespace().find_map(|id| match id {
+            "debian" | "ubuntu" => Some(Self::Apt),
+            "fedora" | "rhel" | "centos" => Some(Self::Dnf),
+            "alpine" => Some(Self::Apk),
+            "arch" => Some(Self::Pacman),
+            _ => None,
+        })
+    }
+    pub fn install_command<'a>(&self, packages: impl IntoIterator<Item = &'a str>) -> String {
+        let command = match self {
+            Self::Apt => "sudo apt-get install -y",
+            Self::Dnf => "sudo dnf install -y",
+            Self::Apk => "sudo apk add",
+            Self::Pacman => "sudo pacman -S --needed",
+        };
+        format!("{} {}", command, packages.into_iter().unique().join(" "))
+    }
+}
+
+/// Missing dependencies with the command installing them on this distro
+#[derive(Debug)]
+pub struct Missing {
+    pub dependencies: Vec<&'static Dependency>,
+    pub package_manager: Option<PackageManager>,
+}
+
+impl Missing {
+    pub fn check(requirements: Vec<&'static Dependency>, cc: Option<&str>) -> Self {
+        Self {
+            dependencies: requirements
+                .into_iter()
+                .filter(|dependency| !dependency.is_found(cc))
+                .collect(),
+            package_manager: PackageManager::detect(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+    pub fn install_command(&self) -> Option<String> {
+        let manager = self.package_manager?;
+        Some(
+            manager.install_command(
+                self.dependencies
+                    .iter()
+                    .map(|dependency| dependency.package(manager)),
+            ),
+        )
+    }
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.dependencies.iter().join(", "))?;
+        if let Some(command) = self.install_command() {
+            write!(f, "\nInstall them by: {}", command)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(version: &str, configure_opts: &[&str], from_git: bool) -> Vec<&'static str> {
+        requirements(version.parse().unwrap(), configure_opts, from_git)
+            .into_iter()
+            .map(|dependency| dependency.name)
+            .collect()
+    }
+
+    #[test]
+    fn requirements_of_version() {
+        assert_eq!(
+            names("8.2.10", &[], false),
+            vec!["cc", "make", "pkg-config", "libxml-2.0", "sqlite3"]
+        );
+        assert_eq!(
+            names(
+                "8.2",
+                &[
+                    "--enable-intl",
+                    "--with-openssl=/opt",
+                    "--without-sqlite3",
+                    "--without-pdo-sqlite"
+                ],
+                true
+            ),
+            vec![
+                "cc",
+                "make",
+                "autoconf",
+                "bison",
+                "re2c",
+                "pkg-config",
+                "libxml-2.0",
+                "openssl",
+                "icu-uc"
+            ]
+        );
+        assert_eq!(
+            names("8.1", &["--disable-all", "--enable-mbstring"], false),
+            vec!["cc", "make", "pkg-config", "oniguruma"]
+        );
+        assert_eq!(names("7.3.33", &["--with-curl"], false), vec!["cc", "make"]);
+    }
+
+    #[test]
+    fn detect_package_manager() {
+        let ubuntu = "NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\n";
+        assert_eq!(
+            PackageManager::from_os_release(ubuntu),
+            Some(PackageManager::Apt)
+        );
+        let rocky = "NAME=\"Rocky Linux\"\nID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n";
+        assert_eq!(
+            PackageManager::from_os_release(rocky),
+            Some(PackageManager::Dnf)
+        );
+        assert_eq!(
+            PackageManager::from_os_release("ID=alpine\n"),
+            Some(PackageManager::Apk)
+        );
+        assert_eq!(PackageManager::from_os_release("ID=gentoo\n"), None);
+
+        let missing = Missing {
+            dependencies: vec![dependency("pkg-config"), dependency("libxml-2.0")],
+            package_manager: Some(PackageManager::Apt),
+        };
+        assert_eq!(
+            missing.to_string(),
+            "pkg-config, libxml-2.0 (pkg-config)\nInstall them by: sudo apt-get install -y pkg-config libxml2-dev"
+        );
+    }
+}
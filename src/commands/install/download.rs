@@ -0,0 +1,237 @@
+use super::progress_reader::ProgressReader;
+use super::PROGRESS_STYLE;
+use crate::http::{self, Transport};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use once_cell::sync::Lazy;
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+
+static UNKNOWN_LENGTH_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
+    ProgressStyle::default_spinner()
+        .template("{prefix:>12.cyan.bold} {spinner} {bytes} ({bytes_per_sec}) {wide_msg}")
+        .unwrap()
+});
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Http(#[from] http::Error),
+
+    #[error("Received an empty file from {0}")]
+    Empty(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    fn is_transient(&self) -> bool {
+        match self {
+            Error::Http(e) => e.is_transient(),
+            Error::Empty(_) | Error::Io(_) => false,
+        }
+    }
+}
+
+pub struct Retry {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            max_retries: 4,
+            initial_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// `<filepath>.part`, which is kept to resume an interrupted download
+pub fn partial_path(filepath: impl AsRef<Path>) -> PathBuf {
+    let mut partial = filepath.as_ref().as_os_str().to_owned();
+    partial.push(".part");
+    PathBuf::from(partial)
+}
+
+/// Downloads `url` into `filepath`.
+/// An interrupted download is resumed by a Range request, and retried with exponential backoff.
+pub fn download(
+    http: &dyn Transport,
+    url: &str,
+    filepath: impl AsRef<Path>,
+    progress_bar: &ProgressBar,
+    retry: &Retry,
+) -> Result<(), Error> {
+    let partial = partial_path(&filepath);
+    let content_length = http.head(url).ok().and_then(|header| header.content_length);
+
+    match content_length {
+        Some(length) => {
+            progress_bar.set_style(PROGRESS_STYLE.clone());
+            progress_bar.set_length(length);
+        }
+        None => {
+            progress_bar.set_style(UNKNOWN_LENGTH_STYLE.clone());
+            progress_bar.enable_steady_tick(Duration::from_millis(100));
+        }
+    }
+
+    let mut retries = 0;
+    loop {
+        match try_download(http, url, &partial, content_length, progress_bar) {
+            Ok(()) => break,
+            Err(Error::Http(http::Error::RangeNotSupported { .. })) => {
+                // start over without counting it as a retry
+                fs::remove_file(&partial)?;
+            }
+            Err(e) if e.is_transient() && retries < retry.max_retries => {
+                let backoff = retry.initial_backoff * 2u32.pow(retries);
+                retries += 1;
+                progress_bar.println(format!(
+                    "{:>12} {} in {:?} ({}/{}): {}",
+                    "Retrying".yellow().bold(),
+                    url,
+                    backoff,
+                    retries,
+                    retry.max_retries,
+                    e.to_string().trim_end()
+                ));
+                thread::sleep(backoff);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if fs::metadata(&partial)?.len() == 0 {
+        fs::remove_file(&partial)?;
+        return Err(Error::Empty(url.to_owned()));
+    }
+    fs::rename(&partial, filepath)?;
+    Ok(())
+}
+
+fn try_download(
+    http: &dyn Transport,
+    url: &str,
+    partial: impl AsRef<Path>,
+    content_length: Option<u64>,
+    progress_bar: &ProgressBar,
+) -> Result<(), Error> {
+    let offset = match (fs::metadata(&partial), content_length) {
+        (Ok(metadata), Some(length)) if metadata.len() <= length => metadata.len(),
+        // can't tell if the partial file is a prefix of the content
+        _ => 0,
+    };
+    progress_bar.set_position(offset);
+    if Some(offset) == content_length {
+        return Ok(());
+    }
+
+    let body = http.get_from(url, offset)?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(offset > 0)
+        .write(true)
+        .truncate(offset == 0)
+        .open(&partial)?;
+    let mut file_writer = BufWriter::new(file);
+    let mut progress_reader = ProgressReader::new(body, progress_bar);
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let read = progress_reader
+            .read(&mut buf)
+            .map_err(|e| http::from_io_error(e, url))?;
+        if read == 0 {
+            break;
+        }
+        file_writer.write_all(&buf[..read])?;
+    }
+    file_writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::tests::{backends, StandIn};
+    use std::sync::atomic::Ordering;
+
+    fn body() -> Vec<u8> {
+        (0..200_000).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn no_wait() -> Retry {
+        Retry {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(10),
+        }
+    }
+
+    #[test]
+    fn resume_dropped_download() {
+        for http in backends() {
+            let server = StandIn::serve(body(), 2);
+            let dir = tempfile::tempdir().unwrap();
+            let filepath = dir.path().join("php-8.2.10.tar.gz");
+
+            download(
+                &*http,
+                &server.url,
+                &filepath,
+                &ProgressBar::hidden(),
+                &no_wait(),
+            )
+            .unwrap();
+
+            assert_eq!(fs::read(&filepath).unwrap(), body());
+            assert_eq!(server.requests.load(Ordering::SeqCst), 3);
+            assert!(!partial_path(&filepath).exists());
+        }
+    }
+
+    #[test]
+    fn resume_from_kept_partial_file() {
+        for http in backends() {
+            let server = StandIn::serve(body(), 0);
+            let dir = tempfile::tempdir().unwrap();
+            let filepath = dir.path().join("php-8.2.10.tar.gz");
+            fs::write(partial_path(&filepath), &body()[..1000]).unwrap();
+
+            download(
+                &*http,
+                &server.url,
+                &filepath,
+                &ProgressBar::hidden(),
+                &no_wait(),
+            )
+            .unwrap();
+            assert_eq!(fs::read(&filepath).unwrap(), body());
+        }
+    }
+
+    #[test]
+    fn give_up_after_retries() {
+        for http in backends() {
+            let server = StandIn::serve(body(), usize::MAX);
+            let dir = tempfile::tempdir().unwrap();
+            let filepath = dir.path().join("php-8.2.10.tar.gz");
+
+            let result = download(
+                &*http,
+                &server.url,
+                &filepath,
+                &ProgressBar::hidden(),
+                &no_wait(),
+            );
+            assert!(matches!(result, Err(Error::Http(_))));
+            assert_eq!(server.requests.load(Ordering::SeqCst), 4);
+            assert!(partial_path(&filepath).exists());
+        }
+    }
+}
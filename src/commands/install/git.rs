@@ -0,0 +1,89 @@
+//! Shallow checkouts of php-src by the `git` command
+
+use std::path::Path;
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't execute `git {command}` because {source}")]
+    FailedExecute {
+        command: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("`git {command}` failed\n{stderr}")]
+    Failed { command: String, stderr: String },
+
+    #[error("Can't find the default branch of {0}")]
+    NoDefaultBranch(String),
+}
+
+/// Commit checked out from a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkout {
+    /// Branch, tag or commit that was fetched
+    pub reference: String,
+    pub commit: String,
+}
+
+fn git(current_dir: Option<&Path>, args: &[&str]) -> Result<String, Error> {
+    let command = args.join(" ");
+    let mut git = Command::new("git");
+    if let Some(current_dir) = current_dir {
+        git.current_dir(current_dir);
+    }
+    let output = git
+        .args(args)
+        .output()
+        .map_err(|source| Error::FailedExecute {
+            command: command.clone(),
+            source,
+        })?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    } else {
+        Err(Error::Failed {
+            command,
+            stderr: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_owned(),
+        })
+    }
+}
+
+/// Branch that `HEAD` of the repository points to
+pub fn default_branch(repository: &str) -> Result<String, Error> {
+    // ref: refs/heads/master	HEAD
+    git(None, &["ls-remote", "--symref", repository, "HEAD"])?
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix("ref: refs/heads/")?
+                .split_whitespace()
+                .next()
+                .map(str::to_owned)
+        })
+        .ok_or_else(|| Error::NoDefaultBranch(repository.to_owned()))
+}
+
+/// Fetches only the commit of `reference` (the default branch if `None`) into `dst_dir`
+pub fn checkout(
+    repository: &str,
+    reference: Option<&str>,
+    dst_dir: impl AsRef<Path>,
+) -> Result<Checkout, Error> {
+    let reference = match reference {
+        Some(reference) => reference.to_owned(),
+        None => default_branch(repository)?,
+    };
+    let dst_dir = Some(dst_dir.as_ref());
+    git(dst_dir, &["init", "--quiet"])?;
+    git(
+        dst_dir,
+        &["fetch", "--quiet", "--depth", "1", repository, &reference],
+    )?;
+    git(dst_dir, &["checkout", "--quiet", "FETCH_HEAD"])?;
+    let commit = git(dst_dir, &["rev-parse", "HEAD"])?;
+    Ok(Checkout { reference, commit })
+}
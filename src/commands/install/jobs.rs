@@ -0,0 +1,191 @@
+//! Default parallelism of `make` within the CPU and memory limits of the cgroup
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Memory a compiler job of PHP may take at most
+const MEMORY_PER_JOB: u64 = 1 << 30;
+
+/// Values of `memory.limit_in_bytes` above this mean no limit in cgroup v1
+const UNLIMITED_MEMORY_V1: u64 = 1 << 60;
+
+/// Number of jobs fitting in the CPUs and memory this process may use
+pub fn default_jobs() -> usize {
+    let proc_cgroup = fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    Limits::read(Path::new(CGROUP_ROOT), &proc_cgroup).jobs(num_cpus::get())
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Limits {
+    cpus: Option<f64>,
+    memory: Option<u64>,
+}
+
+impl Limits {
+    /// Reads the limits of the cgroup given by `/proc/self/cgroup`, whether v2 or v1
+    fn read(root: &Path, proc_cgroup: &str) -> Self {
+        // cgroup v2 has a single hierarchy: `0::/path`
+        if let Some(path) = proc_cgroup
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+        {
+            // either controller may be disabled in the cgroup
+            let cpu_dir = existing_dir(root, path, "cpu.max");
+            let memory_dir = existing_dir(root, path, "memory.max");
+            if cpu_dir.is_some() || memory_dir.is_some() {
+                return Self {
+                    cpus: cpu_dir
+                        .and_then(|dir| read(&dir.join("cpu.max")))
+                        .and_then(|max| parse_cpu_max(&max)),
+                    memory: memory_dir
+                        .and_then(|dir| read(&dir.join("memory.max")))
+                        .and_then(|max| max.parse().ok()),
+                };
+            }
+        }
+        // cgroup v1 has a hierarchy for each controller: `4:cpu,cpuacct:/path`
+        let path_of = |controller: &str| {
+            proc_cgroup.lines().find_map(|line| {
+                let mut fields = line.splitn(3, ':').skip(1);
+                let controllers = fields.next()?;
+                let path = fields.next()?;
+                controllers
+                    .split(',')
+                    .any(|c| c == controller)
+                    .then_some(path)
+            })
+        };
+        let cpu_dir = path_of("cpu")
+            .and_then(|path| existing_dir(&root.join("cpu"), path, "cpu.cfs_quota_us"));
+        let memory_dir = path_of("memory")
+            .and_then(|path| existing_dir(&root.join("memory"), path, "memory.limit_in_bytes"));
+        Self {
+            cpus: cpu_dir.and_then(|dir| {
+                let quota: i64 = read(&dir.join("cpu.cfs_quota_us"))?.parse().ok()?;
+                let period: i64 = read(&dir.join("cpu.cfs_period_us"))?.parse().ok()?;
+                (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+            }),
+            memory: memory_dir.and_then(|dir| {
+                let limit: u64 = read(&dir.join("memory.limit_in_bytes"))?.parse().ok()?;
+                (limit < UNLIMITED_MEMORY_V1).then_some(limit)
+            }),
+        }
+    }
+    fn jobs(&self, cpus: usize) -> usize {
+        let by_cpus = self
+            .cpus
+            .map_or(cpus, |limit| (limit.ceil() as usize).min(cpus));
+        let by_memory = self
+            .memory
+            .map_or(usize::MAX, |limit| (limit / MEMORY_PER_JOB) as usize);
+        by_cpus.min(by_memory).max(1)
+    }
+}
+
+/// The directory of the cgroup, or the root if it's outside of this namespace
+fn existing_dir(root: &Path, path: &str, file: &str) -> Option<PathBuf> {
+    [root.join(path.trim_start_matches('/')), root.to_path_buf()]
+        .into_iter()
+        .find(|dir| dir.join(file).is_file())
+}
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_owned())
+}
+
+/// `cpu.max` is `$MAX $PERIOD`, where `$MAX` may be `max`
+fn parse_cpu_max(cpu_max: &str) -> Option<f64> {
+    let (max, period) = cpu_max.split_once(' ')?;
+    let max: f64 = max.parse().ok()?;
+    let period: f64 = period.parse().ok()?;
+    (period > 0.0).then(|| max / period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn limits_of_cgroup_v2() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("ci.slice/job.scope");
+        write(dir.join("cpu.max"), "150000 100000\n");
+        write(dir.join("memory.max"), "3221225472\n");
+        let limits = Limits::read(root.path(), "0::/ci.slice/job.scope\n");
+        assert_eq!(
+            limits,
+            Limits {
+                cpus: Some(1.5),
+                memory: Some(3 << 30)
+            }
+        );
+        assert_eq!(limits.jobs(16), 2);
+
+        // without limits, in a container seeing its cgroup as the root
+        write(root.path().join("cpu.max"), "max 100000\n");
+        write(root.path().join("memory.max"), "max\n");
+        let limits = Limits::read(root.path(), "0::/\n");
+        assert_eq!(limits, Limits::default());
+        assert_eq!(limits.jobs(16), 16);
+    }
+
+    #[test]
+    fn memory_limit_of_cgroup_v2() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path().join("user.slice/build.scope/memory.max"),
+            "2147483648\n",
+        );
+        let limits = Limits::read(root.path(), "0::/user.slice/build.scope\n");
+        assert_eq!(
+            limits,
+            Limits {
+                cpus: None,
+                memory: Some(2 << 30)
+            }
+        );
+        assert_eq!(limits.jobs(8), 2);
+    }
+
+    #[test]
+    fn limits_of_cgroup_v1() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path().join("cpu/docker/abc/cpu.cfs_quota_us"),
+            "400000\n",
+        );
+        write(
+            root.path().join("cpu/docker/abc/cpu.cfs_period_us"),
+            "100000\n",
+        );
+        write(
+            root.path().join("memory/memory.limit_in_bytes"),
+            "9223372036854771712\n",
+        );
+        let proc_cgroup = "5:memory:/docker/abc\n4:cpu,cpuacct:/docker/abc\n1:name=systemd:/\n";
+        let limits = Limits::read(root.path(), proc_cgroup);
+        assert_eq!(
+            limits,
+            Limits {
+                cpus: Some(4.0),
+                memory: None
+            }
+        );
+        assert_eq!(limits.jobs(2), 2);
+
+        write(
+            root.path().join("memory/memory.limit_in_bytes"),
+            "536870912\n",
+        );
+        assert_eq!(Limits::read(root.path(), proc_cgroup).jobs(8), 1);
+    }
+}
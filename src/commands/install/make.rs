@@ -1,12 +1,20 @@
-use indicatif::{ProgressBar, ProgressStyle};
+use colored::Colorize;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
-use std::path::Path;
+use regex::Regex;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
 use std::sync::mpsc::channel;
 use std::thread;
 use std::time::Duration;
 use thiserror::Error;
 
+/// Lines of the build log shown on a failure
+const MAX_EXCERPT_LINES: usize = 40;
+
 static PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
     ProgressStyle::default_spinner()
         .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ ")
@@ -14,6 +22,18 @@ static PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
         .unwrap()
 });
 
+static COUNTING_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
+    ProgressStyle::default_bar()
+        .template("{prefix:>12.bold.dim} [{bar:25}] {pos}/{len} ({elapsed}, ETA {eta}) {wide_msg}")
+        .unwrap()
+        .progress_chars("=> ")
+});
+
+/// Rules of the objects in a generated Makefile, e.g. `ext/date/php_date.lo: ext/date/php_date.c`
+static OBJECT_RULE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\S+\.lo:").unwrap());
+/// libtool compiling an object, which make prints for each of them
+static COMPILE_LINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"--mode=compile\b").unwrap());
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Can't execute `{command}` because {source}")]
@@ -24,63 +44,232 @@ pub enum Error {
     },
 
     #[error(
-        "build error\n=== Please follow the messages below to resolve dependencies, etc. ===\n\n{0}"
+        "build error\n=== Please follow the messages below to resolve dependencies, etc. ===\n\n{excerpt}\n\nSee the full log: {}",
+        log.display()
     )]
-    ExitFailed(String),
+    ExitFailed { excerpt: String, log: PathBuf },
+
+    #[error("Can't write the build log {}: {source}", path.display())]
+    FailedWriteLog {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// How the commands of the build steps are run
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Parallel jobs of `make`
+    pub jobs: usize,
+    /// Niceness of the commands, `None` to inherit it
+    pub nice: Option<i32>,
+    /// Environment variables set in addition to the inherited ones, such as `CC`
+    pub envs: Vec<(&'static str, String)>,
+}
+
+impl Options {
+    fn command(&self, program: &str) -> std::process::Command {
+        let mut command = match self.nice {
+            Some(nice) => {
+                let mut command = std::process::Command::new("nice");
+                command.args(["-n", &nice.to_string(), program]);
+                command
+            }
+            None => std::process::Command::new(program),
+        };
+        command.envs(self.envs.iter().map(|(name, value)| (name, value)));
+        command
+    }
+}
+
+/// Log file of a build, which the output of every step is appended to
+pub struct Log {
+    path: PathBuf,
+    file: fs::File,
+}
+
+impl Log {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let file = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .read(true)
+                    .open(&path)
+            })
+            .map_err(|source| Error::FailedWriteLog {
+                path: path.clone(),
+                source,
+            })?;
+        Ok(Self { path, file })
+    }
+    fn error(&self, source: std::io::Error) -> Error {
+        Error::FailedWriteLog {
+            path: self.path.clone(),
+            source,
+        }
+    }
+    /// Writes the header of a step and returns the offset its output starts at
+    fn start(&self, command_line: &str) -> Result<u64, Error> {
+        let mut file = &self.file;
+        writeln!(file, "$ {}", command_line)
+            .and_then(|_| file.seek(SeekFrom::End(0)))
+            .map_err(|e| self.error(e))
+    }
+    fn read_from(&self, offset: u64) -> Result<String, Error> {
+        let mut file = &self.file;
+        let mut output = Vec::new();
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_to_end(&mut output))
+            .map_err(|e| self.error(e))?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+}
+
+/// The meaningful tail of a failed step: from the first error, such as `configure: error:`,
+/// or the last lines if there's no error line
+fn excerpt(output: &str) -> String {
+    let lines = output.lines().collect_vec();
+    let start = lines
+        .iter()
+        .position(|line| line.starts_with("configure: error:"))
+        .or_else(|| lines.iter().position(|line| line.contains("error:")))
+        .unwrap_or(lines.len().saturating_sub(MAX_EXCERPT_LINES));
+    lines[start..].iter().take(MAX_EXCERPT_LINES).join("\n")
 }
 
 pub trait Command {
     fn command(&self) -> &'static str;
     fn args(&self) -> Vec<String>;
-    fn order(&self) -> usize;
     fn command_line(&self) -> String {
         format!("{} {}", self.command(), self.args().join(" "))
     }
+    /// Number of lines of the output counted by `is_progress`, if known before running
+    fn total_progress(&self, _current_dir: &Path) -> Option<u64> {
+        None
+    }
+    fn is_progress(&self, _line: &str) -> bool {
+        false
+    }
+    /// Runs the command with both stdout and stderr streamed into `log`,
+    /// passing each line of stdout to `handle_line`
     fn wait(
         &self,
         current_dir: impl AsRef<Path>,
+        log: &Log,
+        options: &Options,
+        mut handle_line: impl FnMut(&str),
         handle_wait: impl Fn(),
-    ) -> Result<std::process::Output, Error> {
-        let command = self.command();
-        let args = self.args();
-        let current_dir = current_dir.as_ref().to_path_buf();
+    ) -> Result<ExitStatus, Error> {
+        let failed_execute = |source| Error::FailedExecute {
+            command: self.command_line(),
+            source,
+        };
+        let mut log_file = log.file.try_clone().map_err(|e| log.error(e))?;
+        let stderr = log.file.try_clone().map_err(|e| log.error(e))?;
+        let mut child = options
+            .command(self.command())
+            .args(self.args())
+            .current_dir(current_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(stderr)
+            .spawn()
+            .map_err(failed_execute)?;
 
+        let stdout = child.stdout.take().unwrap();
         let (tx, rx) = channel();
-        thread::spawn(move || {
-            tx.send(
-                std::process::Command::new(command)
-                    .args(args)
-                    .current_dir(current_dir)
-                    .output(),
-            )
+        let tee = thread::spawn(move || -> std::io::Result<()> {
+            for line in BufReader::new(stdout).split(b'\n') {
+                let line = line?;
+                log_file.write_all(&line)?;
+                log_file.write_all(b"\n")?;
+                tx.send(String::from_utf8_lossy(&line).into_owned()).ok();
+            }
+            Ok(())
         });
-        loop {
-            if let Ok(output) = rx.try_recv() {
-                break output.map_err(|source| Error::FailedExecute {
-                    command: self.command_line(),
-                    source,
-                });
+        let status = loop {
+            rx.try_iter().for_each(|line| handle_line(&line));
+            if let Some(status) = child.try_wait().map_err(failed_execute)? {
+                break status;
             }
             handle_wait();
             thread::sleep(Duration::from_millis(50));
-        }
+        };
+        tee.join().unwrap().map_err(|e| log.error(e))?;
+        rx.try_iter().for_each(|line| handle_line(&line));
+        Ok(status)
     }
-    fn run(&self, current_dir: impl AsRef<Path>) -> Result<(), Error> {
-        let pb = ProgressBar::new(0)
-            .with_style(PROGRESS_STYLE.clone())
-            .with_prefix(format!("[{}/3]", self.order()))
-            .with_message(self.command_line());
+    /// Runs the command as the `step`th of `steps` build steps
+    fn run(
+        &self,
+        current_dir: impl AsRef<Path>,
+        (step, steps): (usize, usize),
+        log: &Log,
+        options: &Options,
+        progress: &MultiProgress,
+    ) -> Result<(), Error> {
+        let current_dir = current_dir.as_ref();
+        let prefix = format!("[{}/{}]", step, steps);
+        let total = self.total_progress(current_dir).filter(|&total| total > 0);
+        let pb = progress.add(
+            match total {
+                Some(total) => ProgressBar::new(total).with_style(COUNTING_STYLE.clone()),
+                None => ProgressBar::new(0).with_style(PROGRESS_STYLE.clone()),
+            }
+            .with_prefix(prefix.clone())
+            .with_message(self.command_line()),
+        );
 
-        let output = self.wait(current_dir, || pb.inc(1))?;
-        pb.finish();
-        if output.status.success() {
+        let offset = log.start(&self.command_line())?;
+        let status = self.wait(
+            current_dir,
+            log,
+            options,
+            |line| {
+                if total.is_some() && self.is_progress(line) {
+                    pb.inc(1);
+                }
+            },
+            || {
+                if total.is_some() {
+                    pb.tick();
+                } else {
+                    pb.inc(1);
+                }
+            },
+        )?;
+        pb.finish_and_clear();
+        progress.remove(&pb);
+        // keep the finished step above the bars of other downloads
+        progress.suspend(|| println!("{:>12}   {}", prefix.bold().dimmed(), self.command_line()));
+        if status.success() {
             Ok(())
         } else {
-            Err(Error::ExitFailed(String::from_utf8(output.stderr).unwrap()))
+            Err(Error::ExitFailed {
+                excerpt: excerpt(&log.read_from(offset)?),
+                log: log.path.clone(),
+            })
         }
     }
 }
 
+/// Generates `configure` of a git checkout
+pub struct Buildconf {}
+impl Command for Buildconf {
+    fn command(&self) -> &'static str {
+        "./buildconf"
+    }
+    fn args(&self) -> Vec<String> {
+        vec!["--force".to_owned()]
+    }
+}
+
 pub struct Configure<'a> {
     pub prefix: &'a Path,
     pub opts: Vec<&'a str>,
@@ -96,21 +285,30 @@ impl Command for Configure<'_> {
             .map(|&s| s.to_owned())
             .collect_vec()
     }
-    fn order(&self) -> usize {
-        1
-    }
 }
 
-pub struct Make {}
+pub struct Make {
+    pub jobs: usize,
+}
 impl Command for Make {
     fn command(&self) -> &'static str {
         "make"
     }
     fn args(&self) -> Vec<String> {
-        vec!["-j".to_owned(), num_cpus::get().to_string()]
+        vec!["-j".to_owned(), self.jobs.to_string()]
+    }
+    /// Objects having a rule in the Makefile generated by `configure`
+    fn total_progress(&self, current_dir: &Path) -> Option<u64> {
+        let makefile = fs::read_to_string(current_dir.join("Makefile")).ok()?;
+        Some(
+            makefile
+                .lines()
+                .filter(|line| OBJECT_RULE.is_match(line))
+                .count() as u64,
+        )
     }
-    fn order(&self) -> usize {
-        2
+    fn is_progress(&self, line: &str) -> bool {
+        COMPILE_LINE.is_match(line)
     }
 }
 
@@ -122,7 +320,111 @@ impl Command for Install {
     fn args(&self) -> Vec<String> {
         vec!["install".to_owned()]
     }
-    fn order(&self) -> usize {
-        3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excerpt_of_failure() {
+        let configure = "checking for cc... cc\nchecking for libxml-2.0 >= 2.9.0... no\nconfigure: error: Package requirements (libxml-2.0 >= 2.9.0) were not met:\n\nNo package 'libxml-2.0' found\n";
+        assert_eq!(
+            excerpt(configure),
+            "configure: error: Package requirements (libxml-2.0 >= 2.9.0) were not met:\n\nNo package 'libxml-2.0' found"
+        );
+        let make = "cc -c main.c\nmain.c:1:1: warning: unused\nmain.c:2:1: error: expected ';'\nmake: *** [main.o] Error 1\n";
+        assert_eq!(
+            excerpt(make),
+            "main.c:2:1: error: expected ';'\nmake: *** [main.o] Error 1"
+        );
+        let output = (1..=100).map(|n| n.to_string()).join("\n");
+        assert_eq!(excerpt(&output).lines().next(), Some("61"));
+    }
+
+    #[test]
+    fn stream_output_into_log() {
+        struct Sh(&'static str);
+        impl Command for Sh {
+            fn command(&self) -> &'static str {
+                "sh"
+            }
+            fn args(&self) -> Vec<String> {
+                vec!["-c".to_owned(), self.0.to_owned()]
+            }
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let log = Log::create(dir.path().join("logs").join("build.log")).unwrap();
+        let progress = MultiProgress::new();
+        let options = Options {
+            jobs: 1,
+            nice: None,
+            envs: Vec::new(),
+        };
+        Sh("echo out; echo err >&2")
+            .run(&dir, (1, 2), &log, &options, &progress)
+            .unwrap();
+        let nice = Options {
+            jobs: 1,
+            nice: Some(5),
+            envs: vec![("CFLAGS", "-O0".to_owned())],
+        };
+        Sh("echo nice: $(nice) $CFLAGS")
+            .run(&dir, (1, 2), &log, &nice, &progress)
+            .unwrap();
+        let result = Sh("echo 'configure: error: missing'; exit 1").run(
+            &dir,
+            (2, 2),
+            &log,
+            &options,
+            &progress,
+        );
+        assert!(
+            matches!(result, Err(Error::ExitFailed { excerpt, .. }) if excerpt == "configure: error: missing")
+        );
+        let content = fs::read_to_string(&log.path).unwrap();
+        assert!(content.starts_with("$ sh -c echo out; echo err >&2\n"));
+        // stdout and stderr may be interleaved differently
+        assert!(content.lines().take(3).contains(&"out"));
+        assert!(content.lines().take(3).contains(&"err"));
+        assert!(content.contains("\nnice: 5 -O0\n"));
+    }
+
+    #[test]
+    fn count_compiled_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let compile = |name: &str| {
+            format!(
+                "{name}.lo: {name}.c\n\t@echo '/bin/sh ./libtool --silent --tag CC --mode=compile cc -c {name}.c -o {name}.lo'\n"
+            )
+        };
+        let makefile = format!("all: a.lo b.lo\n\n{}{}", compile("a"), compile("b"));
+        fs::write(dir.path().join("Makefile"), makefile).unwrap();
+        fs::write(dir.path().join("a.c"), "").unwrap();
+        fs::write(dir.path().join("b.c"), "").unwrap();
+
+        let make = Make { jobs: 2 };
+        assert_eq!(make.total_progress(dir.path()), Some(2));
+        let log = Log::create(dir.path().join("build.log")).unwrap();
+        make.run(
+            &dir,
+            (2, 3),
+            &log,
+            &Options {
+                jobs: 2,
+                nice: None,
+                envs: Vec::new(),
+            },
+            &MultiProgress::new(),
+        )
+        .unwrap();
+        let content = fs::read_to_string(&log.path).unwrap();
+        assert_eq!(
+            content
+                .lines()
+                .filter(|line| make.is_progress(line))
+                .count(),
+            2
+        );
     }
 }
@@ -19,6 +19,8 @@ impl Command for ListLocal {
 
     fn run(&self, config: &Config) -> Result<(), Error> {
         let current_version = Local::current(config);
+        // `list` works offline; `list-remote` refreshes the cache of a remote schedule
+        let schedule = config.cached_support_schedule();
 
         let local_versions = match &self.version {
             Some(request_version) => version::installed_by(request_version, config)
@@ -30,7 +32,11 @@ impl Command for ListLocal {
         for local_version in local_versions {
             let installed = true;
             let used = Some(&local_version) == current_version.as_ref();
-            println!("{}", local_version.to_string_by(installed, used))
+            let support = match local_version {
+                Local::Installed(version) => schedule.support(version),
+                _ => None,
+            };
+            println!("{}", local_version.to_string_by(installed, used, support))
         }
 
         if self.version.is_none() {
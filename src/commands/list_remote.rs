@@ -1,21 +1,49 @@
 use super::{Command, Config};
-use crate::release;
+use crate::decorized::{color::Color, Decorized};
+use crate::parallel;
+use crate::release::{self, Release, Schedule, Support};
 use crate::version;
 use crate::version::Local;
 use crate::version::Version;
+use chrono::NaiveDate;
 use colored::Colorize;
 use itertools::Itertools;
+use std::collections::BTreeMap;
 use thiserror::Error;
 
+const MAX_PARALLEL_FETCHES: usize = 4;
+
 #[derive(clap::Parser, Debug)]
 pub struct ListRemote {
     version: Option<Version>,
-    #[arg(
-        long = "latest-patch",
-        visible_alias = "lp",
-        help = "List latest patch release (avairable only if patch number is NOT specified)"
-    )]
-    only_latest_patch: bool,
+
+    /// List only the latest release of each minor version (avairable only if patch number is NOT specified)
+    #[arg(long, visible_aliases = ["latest-patch", "lp"])]
+    latest_per_minor: bool,
+
+    /// Show the release date, support status, security fixes and install status
+    #[arg(short, long)]
+    long: bool,
+
+    /// List only the versions of branches still supported
+    #[arg(long, conflicts_with = "eol")]
+    supported: bool,
+
+    /// List only the versions of branches at end of life
+    #[arg(long)]
+    eol: bool,
+
+    /// List only the releases tagged as security fixes
+    #[arg(long)]
+    security_only: bool,
+
+    /// List only the releases on or after the date (e.g. `2023-01-01`)
+    #[arg(long, value_name = "DATE")]
+    since: Option<NaiveDate>,
+
+    /// List only the installed versions
+    #[arg(long)]
+    installed: bool,
 }
 
 #[derive(Error, Debug)]
@@ -30,9 +58,9 @@ impl Command for ListRemote {
     fn run(&self, config: &Config) -> Result<(), Error> {
         let query_versions = match &self.version {
             Some(version) => {
-                if self.only_latest_patch && version.patch_version().is_some() {
+                if self.latest_per_minor && version.patch_version().is_some() {
                     println!(
-                        "{}: '--latest-patch' is available only if patch number is NOT specified: {}",
+                        "{}: '--latest-per-minor' is available only if patch number is NOT specified: {}",
                         "warning".yellow().bold(),
                         version
                     );
@@ -52,88 +80,178 @@ impl Command for ListRemote {
 
         let installed_versions = version::installed(config).collect_vec();
         let current_version = Local::current(config);
+        let schedule = config.support_schedule();
 
-        for query_version in query_versions {
-            let releases = release::fetch_all(query_version)?;
-            let remote_versions = releases.keys();
-
-            let remote_versions = if self.only_latest_patch {
-                filter_latest_patch(remote_versions).collect_vec()
-            } else {
-                remote_versions.collect_vec()
-            };
-
-            for &remote_version in remote_versions {
-                let installed = installed_versions.contains(&remote_version);
-                let remote_version = Local::Installed(remote_version);
-                let used = Some(&remote_version) == current_version.as_ref();
-                println!("{}", remote_version.to_string_by(installed, used))
-            }
-        }
+        let http = config.http();
+        let release_source = config.release_source(&*http);
+        // print each major in order as soon as it and the preceding ones arrive
+        parallel::for_each_ordered(
+            query_versions,
+            MAX_PARALLEL_FETCHES,
+            |query_version| release_source.fetch_all(query_version),
+            |releases| {
+                let releases = releases?;
+                for (&remote_version, release) in
+                    self.select(&releases, &schedule, &installed_versions)
+                {
+                    let installed = installed_versions.contains(&remote_version);
+                    let support = schedule.support(remote_version);
+                    let remote_version = Local::Installed(remote_version);
+                    let used = Some(&remote_version) == current_version.as_ref();
+                    if self.long {
+                        println!(
+                            "{}",
+                            long_line(&remote_version, release, support, installed, used)
+                        )
+                    } else {
+                        println!("{}", remote_version.to_string_by(installed, used, support))
+                    }
+                }
+                Ok::<_, Error>(())
+            },
+        )?;
         Ok(())
     }
 }
 
-fn filter_latest_patch<'a, I>(versions: I) -> impl Iterator<Item = &'a Version>
+impl ListRemote {
+    /// Releases passing the filters, in ascending order
+    fn select<'a>(
+        &self,
+        releases: &'a BTreeMap<Version, Release>,
+        schedule: &Schedule,
+        installed_versions: &[Version],
+    ) -> Vec<(&'a Version, &'a Release)> {
+        let selected = releases.iter().filter(|(&version, release)| {
+            let support = schedule.support(version);
+            (!self.supported || support.is_some_and(|support| support != Support::EndOfLife))
+                && (!self.eol || support == Some(Support::EndOfLife))
+                && (!self.security_only || release.is_security())
+                && self.since.is_none_or(|since| release.date >= since)
+                && (!self.installed || installed_versions.contains(&version))
+        });
+        if self.latest_per_minor {
+            latest_per_minor(selected).collect()
+        } else {
+            selected.collect()
+        }
+    }
+}
+
+/// Keeps the last release of each run of the same minor version
+fn latest_per_minor<'a, I>(releases: I) -> impl Iterator<Item = (&'a Version, &'a Release)>
 where
-    I: Iterator<Item = &'a Version> + DoubleEndedIterator,
+    I: Iterator<Item = (&'a Version, &'a Release)>,
 {
-    let mut latest_patch: Option<&'a Version> = None;
-    let mut latest_patches = versions
-        .rev()
-        .filter_map(|version| {
-            if latest_patch.is_none()
-                || latest_patch.unwrap().minor_version() != version.minor_version()
-            {
-                latest_patch.replace(version)
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    latest_patches.push(latest_patch.unwrap());
-    latest_patches.into_iter().rev()
+    releases.coalesce(|previous, next| {
+        if previous.0.is_same_minor(*next.0) {
+            Ok(next)
+        } else {
+            Err((previous, next))
+        }
+    })
+}
+
+fn long_line(
+    version: &Local,
+    release: &Release,
+    support: Option<Support>,
+    installed: bool,
+    used: bool,
+) -> String {
+    format!(
+        "{:<2}{} {} {} {:<8} {}",
+        if installed { "*" } else { "" },
+        format!("{:<8}", version).color(<Version as Decorized>::Color::color()),
+        release.date,
+        Support::column(support),
+        if release.is_security() {
+            "security".red()
+        } else {
+            "".normal()
+        },
+        if used {
+            "current".green().bold()
+        } else if installed {
+            "installed".normal()
+        } else {
+            "".normal()
+        }
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::Parser;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/releases.json");
+    const SCHEDULE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/release/schedule.json");
 
     #[test]
     fn version_not_specified() {
         let base_dir = tempfile::tempdir().unwrap();
-        let config = Config::default().with_base_dir(base_dir);
-        let cmd = ListRemote {
-            version: None,
-            only_latest_patch: false,
-        };
+        let config = Config::default()
+            .with_base_dir(base_dir)
+            .with_release_source(FIXTURE)
+            .with_support_schedule(SCHEDULE);
+        let cmd = ListRemote::try_parse_from(["list-remote"]).unwrap();
         assert!(cmd.run(&config).is_ok());
     }
     #[test]
     fn version_specified() {
         let base_dir = tempfile::tempdir().unwrap();
-        let config = Config::default().with_base_dir(base_dir);
-        let cmd = ListRemote {
-            version: Some("7.2".parse().unwrap()),
-            only_latest_patch: false,
-        };
+        let config = Config::default()
+            .with_base_dir(base_dir)
+            .with_release_source(FIXTURE)
+            .with_support_schedule(SCHEDULE);
+        let cmd = ListRemote::try_parse_from(["list-remote", "7.2"]).unwrap();
         assert!(cmd.run(&config).is_ok());
     }
+    #[test]
+    fn latest_patch_of_version() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let config = Config::default()
+            .with_base_dir(base_dir)
+            .with_release_source(FIXTURE)
+            .with_support_schedule(SCHEDULE);
+        let cmd = ListRemote::try_parse_from(["list-remote", "--lp", "--long", "7.4"]).unwrap();
+        assert!(cmd.run(&config).is_ok());
+    }
+    #[test]
+    fn filters() {
+        let releases: BTreeMap<Version, Release> =
+            serde_json::from_slice(&std::fs::read(FIXTURE).unwrap()).unwrap();
+        let schedule = Schedule::parse(
+            "test",
+            br#"[
+                { "cycle": "8.2", "releaseDate": "2022-12-08", "support": false, "eol": false },
+                { "cycle": "7.4", "releaseDate": "2019-11-28", "support": "2021-11-28", "eol": "2022-11-28" }
+            ]"#,
+        )
+        .unwrap();
+        let installed = vec!["7.4.32".parse().unwrap()];
+        let select = |args: &[&str]| {
+            let cmd =
+                ListRemote::try_parse_from(["list-remote"].iter().chain(args.iter())).unwrap();
+            cmd.select(&releases, &schedule, &installed)
+                .into_iter()
+                .map(|(version, _)| version.to_string())
+                .collect_vec()
+        };
+        assert_eq!(select(&[]), vec!["7.4.32", "7.4.33", "8.2.10", "8.3.0RC1"]);
+        assert_eq!(
+            select(&["--latest-per-minor"]),
+            vec!["7.4.33", "8.2.10", "8.3.0RC1"]
+        );
+        assert_eq!(select(&["--supported"]), vec!["8.2.10"]);
+        assert_eq!(select(&["--eol", "--lp"]), vec!["7.4.33"]);
+        assert_eq!(select(&["--security-only"]), vec!["7.4.32", "7.4.33"]);
+        assert_eq!(
+            select(&["--since", "2023-01-01"]),
+            vec!["8.2.10", "8.3.0RC1"]
+        );
+        assert_eq!(select(&["--installed"]), vec!["7.4.32"]);
+        assert!(ListRemote::try_parse_from(["list-remote", "--supported", "--eol"]).is_err());
+    }
 }
-
-// TODO: can't get last itm
-// fn filter_latest_patch<'a, T>(versions: T) -> impl Iterator<Item = &'a Version>
-// where
-//     T: Iterator<Item = &'a Version> + DoubleEndedIterator,
-// {
-//     let mut latest_patch: Option<&'a Version> = None;
-//     versions
-//         .map(move |version| match latest_patch {
-//             Some(latest) if latest.minor_version().unwrap() == version.minor_version().unwrap() => {
-//                 latest_patch.replace(version);
-//                 None
-//             }
-//             _ => latest_patch.replace(version),
-//         })
-//         .filter_map(|e| e)
-// }
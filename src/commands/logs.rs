@@ -0,0 +1,110 @@
+use super::{Command, Config};
+use crate::decorized::Decorized;
+use crate::version::{Local, Version};
+use itertools::Itertools;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+pub struct Logs {
+    /// Version or label of the build (e.g. `8.1`, `8.4-dev`)
+    version: Local,
+
+    /// List the log files of the builds instead of printing the latest one
+    #[arg(long)]
+    list: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't find build logs of '{0}'")]
+    NotFound(Local),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl Command for Logs {
+    type Error = Error;
+
+    fn run(&self, config: &Config) -> Result<(), Error> {
+        let not_found = || Error::NotFound(self.version.clone());
+        let log_dir = self.log_dir(config).ok_or_else(not_found)?;
+        let logs = fs::read_dir(&log_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            // named by the start time of the build
+            .sorted()
+            .collect_vec();
+
+        if self.list {
+            for log in &logs {
+                println!("{}", log.display().decorized());
+            }
+            return Ok(());
+        }
+        let latest = logs.last().ok_or_else(not_found)?;
+        io::copy(&mut fs::File::open(latest)?, &mut io::stdout())?;
+        Ok(())
+    }
+}
+
+impl Logs {
+    /// Log directory of the label, or of the latest version included by the requested one
+    fn log_dir(&self, config: &Config) -> Option<PathBuf> {
+        let logs_dir = config.logs_dir();
+        match &self.version {
+            Local::Installed(request) => fs::read_dir(&logs_dir)
+                .ok()?
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<Version>().ok())
+                .filter(|version| request.includes(version))
+                .max()
+                .map(|version| logs_dir.join(version.to_string())),
+            Local::Labeled(label) => Some(logs_dir.join(label)).filter(|dir| dir.is_dir()),
+            Local::System => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::install::tests::fake_source;
+    use crate::commands::install::Install;
+    use clap::Parser;
+
+    #[test]
+    fn logs_of_build() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.10");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+        Install::try_parse_from(["install", "8.2"])
+            .unwrap()
+            .run(&config)
+            .unwrap();
+
+        let log_dir = Logs::try_parse_from(["logs", "8"])
+            .unwrap()
+            .log_dir(&config)
+            .unwrap();
+        assert_eq!(log_dir, config.logs_dir().join("8.2.10"));
+        let log = fs::read_dir(&log_dir).unwrap().next().unwrap().unwrap();
+        let content = fs::read_to_string(log.path()).unwrap();
+        assert!(content.starts_with("$ ./configure --prefix="));
+        assert!(content.contains("$ make install"));
+
+        for args in [vec!["logs", "8.2"], vec!["logs", "--list", "8.2.10"]] {
+            Logs::try_parse_from(args).unwrap().run(&config).unwrap();
+        }
+        assert!(matches!(
+            Logs::try_parse_from(["logs", "7.4"]).unwrap().run(&config),
+            Err(Error::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use super::{Command, Config};
+use crate::decorized::Decorized;
+use crate::release::{self, Release};
+use crate::version::{self, Version};
+use colored::Colorize;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+pub struct Outdated {
+    /// Print the outdated versions as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    FailedFetchRelease(#[from] release::FetchError),
+
+    #[error("{0} installed version(s) have a newer patch release")]
+    Outdated(usize),
+}
+
+/// Installed version with a newer patch release
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct Entry {
+    version: Version,
+    latest: Version,
+    /// Some of the newer releases fix security issues
+    security: bool,
+}
+
+impl Command for Outdated {
+    type Error = Error;
+
+    fn run(&self, config: &Config) -> Result<(), Error> {
+        let installed = version::installed(config).collect_vec();
+        let releases = fetch_releases_of(config, &installed)?;
+        let outdated = installed
+            .into_iter()
+            .filter_map(|version| outdated(version, &releases))
+            .collect_vec();
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&outdated).unwrap());
+        } else {
+            for entry in &outdated {
+                println!(
+                    "{:>12} {} -> {}{}",
+                    "Outdated".yellow().bold(),
+                    entry.version.decorized_with_prefix(),
+                    entry.latest.decorized(),
+                    if entry.security {
+                        format!(" ({})", "security".red().bold())
+                    } else {
+                        String::new()
+                    }
+                );
+            }
+        }
+        if outdated.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Outdated(outdated.len()))
+        }
+    }
+}
+
+/// Releases of the major versions of `versions`
+pub(super) fn fetch_releases_of(
+    config: &Config,
+    versions: &[Version],
+) -> Result<BTreeMap<Version, Release>, release::FetchError> {
+    let http = config.http();
+    let release_source = config.release_source(&*http);
+    let mut releases = BTreeMap::new();
+    for major in versions
+        .iter()
+        .map(|version| version.major_version())
+        .unique()
+    {
+        releases.extend(release_source.fetch_all(Version::from_major(major))?);
+    }
+    Ok(releases)
+}
+
+/// Stable releases of the same minor version newer than `version`
+pub(super) fn newer_patches(
+    version: Version,
+    releases: &BTreeMap<Version, Release>,
+) -> impl Iterator<Item = (&Version, &Release)> {
+    releases.iter().filter(move |(release_version, _)| {
+        release_version.is_same_minor(version)
+            && !release_version.is_pre_release()
+            && **release_version > version
+    })
+}
+
+fn outdated(version: Version, releases: &BTreeMap<Version, Release>) -> Option<Entry> {
+    let newer = newer_patches(version, releases).collect_vec();
+    let (latest, _) = newer.last()?;
+    Some(Entry {
+        version,
+        latest: **latest,
+        security: newer.iter().any(|(_, release)| release.is_security()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn releases(json: &str) -> BTreeMap<Version, Release> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn find_outdated() {
+        let releases = releases(
+            r#"{
+                "8.1.12": {"date": "27 Oct 2022", "tags": [], "source": [], "version": "8.1.12"},
+                "8.1.13": {"date": "24 Nov 2022", "tags": ["security"], "source": [], "version": "8.1.13"},
+                "8.1.14": {"date": "05 Jan 2023", "tags": [], "source": [], "version": "8.1.14"},
+                "8.2.0": {"date": "08 Dec 2022", "tags": [], "source": [], "version": "8.2.0"}
+            }"#,
+        );
+        assert_eq!(
+            outdated("8.1.12".parse().unwrap(), &releases),
+            Some(Entry {
+                version: "8.1.12".parse().unwrap(),
+                latest: "8.1.14".parse().unwrap(),
+                security: true,
+            })
+        );
+        assert_eq!(
+            outdated("8.1.13".parse().unwrap(), &releases).map(|entry| entry.security),
+            Some(false)
+        );
+        assert_eq!(outdated("8.1.14".parse().unwrap(), &releases), None);
+        assert_eq!(outdated("8.2.0".parse().unwrap(), &releases), None);
+    }
+}
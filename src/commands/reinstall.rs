@@ -0,0 +1,204 @@
+use super::install;
+use super::{Command, Config};
+use crate::manifest::{self, Manifest};
+use crate::version::Local;
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+pub struct Reinstall {
+    /// Installed version or label to build again (e.g. `8.1.12`, `8.4-dev`)
+    version: Local,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't find installed version '{0}'")]
+    NotInstalled(Local),
+
+    #[error("{0} has no install manifest to rebuild with; install it again instead")]
+    NoManifest(Local),
+
+    #[error(transparent)]
+    FailedReadManifest(#[from] manifest::Error),
+
+    #[error(transparent)]
+    FailedInstall(#[from] install::Error),
+
+    #[error("Can't restore the old build of {install}: {source}\nRename {backup} back to {install} to recover it")]
+    FailedRestore {
+        backup: PathBuf,
+        install: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Command for Reinstall {
+    type Error = Error;
+
+    fn run(&self, config: &Config) -> Result<(), Error> {
+        restore_interrupted(config)?;
+        let installed = self
+            .version
+            .installed(config)
+            .ok_or_else(|| Error::NotInstalled(self.version.clone()))?;
+        let install_dir = config.versions_dir().join(installed.to_string());
+        let previous =
+            Manifest::read(&install_dir)?.ok_or_else(|| Error::NoManifest(installed.clone()))?;
+        rebuild_in_place(config, &installed, &previous)
+    }
+}
+
+/// Puts back the old builds left by an interrupted rebuild, unless the new one has been finished
+fn restore_interrupted(config: &Config) -> Result<(), Error> {
+    let versions_dir = config.versions_dir();
+    for entry in fs::read_dir(&versions_dir)? {
+        let backup_dir = entry?.path();
+        let install_name = backup_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix('.')?.strip_suffix(".previous"));
+        let Some(install_name) = install_name else {
+            continue;
+        };
+        let install_dir = versions_dir.join(install_name);
+        // the manifest is written last
+        if Manifest::filepath(&install_dir).is_file() {
+            fs::remove_dir_all(&backup_dir)?;
+            continue;
+        }
+        println!(
+            "{}: Restoring {} left by an interrupted rebuild",
+            "warning".yellow().bold(),
+            install_name
+        );
+        restore(&backup_dir, &install_dir)?;
+    }
+    Ok(())
+}
+
+fn restore(backup_dir: &Path, install_dir: &Path) -> Result<(), Error> {
+    let restored = if install_dir.exists() {
+        fs::remove_dir_all(install_dir).and_then(|_| fs::rename(backup_dir, install_dir))
+    } else {
+        fs::rename(backup_dir, install_dir)
+    };
+    restored.map_err(|source| Error::FailedRestore {
+        backup: backup_dir.to_owned(),
+        install: install_dir.to_owned(),
+        source,
+    })
+}
+
+/// Builds an install again into the same directory, restoring the old one if the build fails
+fn rebuild_in_place(config: &Config, installed: &Local, previous: &Manifest) -> Result<(), Error> {
+    let install_dir = config.versions_dir().join(installed.to_string());
+    // hidden from the installed versions while rebuilding
+    let backup_dir = config
+        .versions_dir()
+        .join(format!(".{}.previous", installed));
+    if backup_dir.exists() {
+        fs::remove_dir_all(&backup_dir)?;
+    }
+    fs::rename(&install_dir, &backup_dir)?;
+    match install::rebuild(config, previous) {
+        Ok(()) => {
+            fs::remove_dir_all(&backup_dir)?;
+            Ok(())
+        }
+        Err(e) => {
+            restore(&backup_dir, &install_dir)?;
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::install::tests::fake_source;
+    use clap::Parser;
+
+    #[test]
+    fn rebuild_with_recorded_options() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.10");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+        install::Install::try_parse_from(["install", "8.2", "--", "-cgi"])
+            .unwrap()
+            .run(&config)
+            .unwrap();
+
+        let install_dir = config.versions_dir().join("8.2.10");
+        let php = install_dir.join("bin").join("php");
+        fs::write(&php, "broken").unwrap();
+        let reinstall = |version: &str| {
+            Reinstall {
+                version: version.parse().unwrap(),
+            }
+            .run(&config)
+        };
+        reinstall("8.2").unwrap();
+        assert_ne!(fs::read_to_string(&php).unwrap(), "broken");
+        let manifest = Manifest::read(&install_dir).unwrap().unwrap();
+        assert_eq!(manifest.configure_opts, vec!["--disable-cgi"]);
+
+        // the old build is restored if the rebuild fails
+        fs::write(&php, "kept").unwrap();
+        for entry in fs::read_dir(&sources).unwrap() {
+            fs::remove_file(entry.unwrap().path()).unwrap();
+        }
+        assert!(matches!(reinstall("8.2.10"), Err(Error::FailedInstall(_))));
+        assert_eq!(fs::read_to_string(&php).unwrap(), "kept");
+
+        assert!(matches!(reinstall("8.1"), Err(Error::NotInstalled(_))));
+        fs::remove_file(Manifest::filepath(&install_dir)).unwrap();
+        assert!(matches!(reinstall("8.2"), Err(Error::NoManifest(_))));
+    }
+
+    #[test]
+    fn restore_after_interrupted_rebuild() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.2.10");
+        fake_source(sources.path(), "8.1.24");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+        install::Install::try_parse_from(["install", "8.2", "8.1"])
+            .unwrap()
+            .run(&config)
+            .unwrap();
+        let versions_dir = config.versions_dir();
+
+        // stopped while building 8.2.10, and while removing the old build of 8.1.24
+        fs::rename(
+            versions_dir.join("8.2.10"),
+            versions_dir.join(".8.2.10.previous"),
+        )
+        .unwrap();
+        fs::create_dir_all(versions_dir.join("8.2.10").join("bin")).unwrap();
+        fs::create_dir(versions_dir.join(".8.1.24.previous")).unwrap();
+        Reinstall {
+            version: "8.1".parse().unwrap(),
+        }
+        .run(&config)
+        .unwrap();
+        assert!(Manifest::read(versions_dir.join("8.2.10"))
+            .unwrap()
+            .is_some());
+        assert!(!versions_dir.join(".8.2.10.previous").exists());
+        assert!(!versions_dir.join(".8.1.24.previous").exists());
+    }
+}
@@ -3,19 +3,20 @@ use crate::decorized::Decorized;
 use crate::symlink;
 use crate::version;
 use crate::version::Local;
-use crate::version::Version;
 use std::fs;
 use thiserror::Error;
 
 #[derive(clap::Parser, Debug)]
 pub struct Uninstall {
-    version: Version,
+    version: Local,
 }
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Can't find installed version '{0}'")]
-    NotInstalled(Version),
+    NotInstalled(Local),
+    #[error("Can't uninstall the system version")]
+    SystemVersion,
     #[error(transparent)]
     NoMultiShellPath(#[from] ConfigError),
 }
@@ -24,11 +25,18 @@ impl Command for Uninstall {
     type Error = Error;
 
     fn run(&self, config: &Config) -> Result<(), Error> {
-        let uninstall_version = version::installed(config)
-            .find(|installed_version| installed_version == &self.version)
-            .ok_or(Error::NotInstalled(self.version))?;
+        let uninstall_version = match &self.version {
+            Local::Installed(version) => version::installed(config)
+                .find(|installed_version| installed_version == version)
+                .map(Local::Installed),
+            Local::Labeled(label) => version::labeled(config)
+                .find(|labeled| labeled == label)
+                .map(Local::Labeled),
+            Local::System => return Err(Error::SystemVersion),
+        }
+        .ok_or_else(|| Error::NotInstalled(self.version.clone()))?;
 
-        if Local::current(config) == Some(Local::Installed(uninstall_version)) {
+        if Local::current(config).as_ref() == Some(&uninstall_version) {
             symlink::remove(config.multishell_path()?).expect("Can't remove symlink!");
         }
 
@@ -0,0 +1,233 @@
+use super::install;
+use super::{Command, Config, ConfigError};
+use crate::decorized::Decorized;
+use crate::manifest::{self, Manifest};
+use crate::release;
+use crate::symlink;
+use crate::version::{self, alias, Local, Version};
+use colored::Colorize;
+use itertools::Itertools;
+use std::fs;
+use thiserror::Error;
+
+#[derive(clap::Parser, Debug)]
+pub struct Upgrade {
+    /// Installed version to upgrade to the latest patch of its minor version (e.g. `8.1`)
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    version: Option<Version>,
+
+    /// Upgrade the latest installed patch of each minor version
+    #[arg(long)]
+    all: bool,
+
+    /// Uninstall the old patch versions after upgrading
+    #[arg(long)]
+    remove_old: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't find installed version '{0}'")]
+    NotInstalled(Version),
+
+    #[error("{0} has no install manifest to upgrade with; install the new version instead")]
+    NoManifest(Version),
+
+    #[error(transparent)]
+    FailedReadManifest(#[from] manifest::Error),
+
+    #[error(transparent)]
+    FailedFetchRelease(#[from] release::FetchError),
+
+    #[error(transparent)]
+    FailedInstall(#[from] install::Error),
+
+    #[error("Can't move an alias: {0}")]
+    FailedMoveAlias(#[from] alias::Error),
+
+    #[error(transparent)]
+    NoMultiShellPath(#[from] ConfigError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Command for Upgrade {
+    type Error = Error;
+
+    fn run(&self, config: &Config) -> Result<(), Error> {
+        let old_versions = match self.version {
+            Some(version) => vec![version::latest_installed_by(&version, config)
+                .ok_or(Error::NotInstalled(version))?],
+            None => version::installed(config)
+                .into_group_map_by(|version| (version.major_version(), version.minor_version()))
+                .into_values()
+                .filter_map(|versions| versions.into_iter().max())
+                .sorted()
+                .collect(),
+        };
+
+        let http = config.http();
+        let release_source = config.release_source(&*http);
+        let mut upgrades = Vec::new();
+        let mut plans = Vec::new();
+        for old_version in old_versions {
+            let minor_version = Version::from_numbers(
+                old_version.major_version(),
+                old_version.minor_version(),
+                None,
+            );
+            let release = release_source.fetch_latest(minor_version)?;
+            let new_version = release.version.unwrap();
+            if new_version <= old_version {
+                println!(
+                    "{:>12} {}",
+                    "Up to date".green().bold(),
+                    old_version.decorized_with_prefix()
+                );
+                continue;
+            }
+            let Some(previous) =
+                Manifest::read(config.versions_dir().join(old_version.to_string()))?
+            else {
+                if self.all {
+                    println!(
+                        "{}: Skip {} without an install manifest",
+                        "warning".yellow().bold(),
+                        old_version.decorized_with_prefix()
+                    );
+                    continue;
+                }
+                return Err(Error::NoManifest(old_version));
+            };
+            println!(
+                "{:>12} {} -> {}",
+                "Upgrading".green().bold(),
+                old_version.decorized_with_prefix(),
+                new_version.decorized_with_prefix()
+            );
+            upgrades.push((old_version, new_version));
+            if config.versions_dir().join(new_version.to_string()).exists() {
+                continue;
+            }
+            let mut manifest = Manifest::new(new_version);
+            manifest.variants = previous.variants;
+            manifest.configure_opts = previous.configure_opts;
+            manifest.toolchain = previous.toolchain;
+            plans.push((release, manifest));
+        }
+        install::Install::default().install_releases(config, plans)?;
+
+        for (old_version, new_version) in upgrades {
+            move_aliases(config, old_version, new_version)?;
+            if self.remove_old {
+                remove(config, old_version, new_version)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Points the aliases, including the default, of the old version to the new one
+fn move_aliases(config: &Config, old_version: Version, new_version: Version) -> Result<(), Error> {
+    let old_version = Local::Installed(old_version);
+    let new_version = Local::Installed(new_version);
+    for (alias, linked_version) in alias::read_aliases_dir(config).sorted() {
+        if linked_version == old_version {
+            alias.link(&new_version, config.aliases_dir())?;
+            println!(
+                "{:>12} {}@ -> {}",
+                "Moved".green().bold(),
+                alias.decorized(),
+                new_version.decorized()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Uninstalls the old version, switching the current shell to the new one if it used the old
+fn remove(config: &Config, old_version: Version, new_version: Version) -> Result<(), Error> {
+    if Local::current(config) == Some(Local::Installed(old_version)) {
+        let multishell_path = config.multishell_path()?;
+        symlink::remove(multishell_path)?;
+        let new_dir = config.versions_dir().join(new_version.to_string());
+        symlink::link(new_dir.join("bin"), multishell_path)?;
+    }
+    let old_dir = config.versions_dir().join(old_version.to_string());
+    fs::remove_dir_all(&old_dir)?;
+    println!(
+        "{:>12} {}",
+        "Removed".green().bold(),
+        old_version.decorized_with_prefix()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+    use crate::commands::install::tests::fake_source;
+    use clap::Parser;
+
+    #[test]
+    fn upgrade_with_recorded_options() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let sources = tempfile::tempdir().unwrap();
+        fake_source(sources.path(), "8.1.12");
+        let config = Config::default()
+            .with_base_dir(&base_dir)
+            .with_release_source(sources.path().to_str().unwrap())
+            .with_skip_preflight();
+        install::Install::try_parse_from(["install", "8.1", "--", "-cgi"])
+            .unwrap()
+            .run(&config)
+            .unwrap();
+        for (alias, version) in [
+            ("stable", "8.1.12"),
+            ("default", "8.1.12"),
+            ("minor", "8.1"),
+        ] {
+            commands::Alias::try_parse_from(["alias", alias, version])
+                .unwrap()
+                .run(&config)
+                .unwrap();
+        }
+
+        fake_source(sources.path(), "8.1.27");
+        let upgrade = Upgrade::try_parse_from(["upgrade", "--remove-old", "8.1"]).unwrap();
+        upgrade.run(&config).unwrap();
+
+        let manifest = Manifest::read(config.versions_dir().join("8.1.27"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(manifest.configure_opts, vec!["--disable-cgi"]);
+        assert!(!config.versions_dir().join("8.1.12").exists());
+        let aliases = alias::read_aliases_dir(&config)
+            .map(|(alias, version)| (alias.to_string(), version.to_string()))
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            aliases,
+            vec![
+                ("default".to_owned(), "8.1.27".to_owned()),
+                ("minor".to_owned(), "8.1".to_owned()),
+                ("stable".to_owned(), "8.1.27".to_owned()),
+            ]
+        );
+
+        // already the latest
+        Upgrade::try_parse_from(["upgrade", "--all"])
+            .unwrap()
+            .run(&config)
+            .unwrap();
+        assert!(Upgrade::try_parse_from(["upgrade"]).is_err());
+        assert!(matches!(
+            Upgrade::try_parse_from(["upgrade", "7.4"])
+                .unwrap()
+                .run(&config),
+            Err(Error::NotInstalled(_))
+        ));
+    }
+}
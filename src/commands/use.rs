@@ -38,6 +38,9 @@ pub enum Error {
     #[error("Can't find installed version '{version}' {source}")]
     NotInstalled { version: Version, source: Source },
 
+    #[error("Can't find installed '{label}' {source}")]
+    NotInstalledLabel { label: String, source: Source },
+
     #[error(transparent)]
     NoMultiShellPath(#[from] ConfigError),
 
@@ -68,6 +71,12 @@ impl Command for Use {
                 RequestVersion::Installed(version) => {
                     (Local::Installed(*version), Source::Installed(*version))
                 }
+                // a labeled install takes precedence over an alias of the same name
+                RequestVersion::Alias(alias)
+                    if version::labeled(config).any(|label| label == alias.to_string()) =>
+                {
+                    (Local::Labeled(alias.to_string()), Source::Labeled)
+                }
                 RequestVersion::Alias(alias) => {
                     let version = alias.resolve(config.aliases_dir())?;
                     outln!(
@@ -104,6 +113,19 @@ impl Command for Use {
 
                 outln!(!self.quiet, "Using {}", use_version.decorized_with_prefix());
             }
+            Local::Labeled(label) => {
+                let label_dir = config.versions_dir().join(&label);
+                if !label_dir.join("bin").join("php").is_file() {
+                    return Err(Error::NotInstalledLabel { label, source });
+                }
+                replace_multishell_path(label_dir.join("bin"), config)?;
+
+                outln!(
+                    !self.quiet,
+                    "Using {}",
+                    Local::Labeled(label).decorized_with_prefix()
+                );
+            }
             Local::System => {
                 let system_path = version::system::path().ok_or(Error::NoSystemVersion)?;
                 replace_multishell_path(&system_path, config)?;
@@ -144,6 +166,7 @@ impl FromStr for RequestVersion {
 #[derive(Error, Debug)]
 pub enum Source {
     Installed(Version),
+    Labeled,
     Alias(Alias),
     File(PathBuf),
     System,
@@ -152,7 +175,7 @@ pub enum Source {
 impl Display for Source {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Source::Installed(_) => String::new().fmt(f),
+            Source::Installed(_) | Source::Labeled => String::new().fmt(f),
             Source::Alias(alias) => format!("specified by alias '{}'", alias).fmt(f),
             Source::File(path) => format!("specified by version-file '{}'", path.display()).fmt(f),
             Source::System => String::new().fmt(f),
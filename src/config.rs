@@ -1,30 +1,325 @@
+use crate::archive;
+use crate::cache::Period;
+use crate::clap_enum_variants;
+use crate::http::{self, Backend, Transport};
+use crate::release::{self, ReleaseCache, ReleaseSource, Schedule};
+use crate::signature;
+use colored::Colorize;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 
+pub mod file;
+
+pub use file::ConfigFile;
+
+const DEFAULT_CONNECT_TIMEOUT: u64 = 30;
+const DEFAULT_READ_TIMEOUT: u64 = 60;
+
 #[derive(clap::Parser, Debug, Default)]
 pub struct Config {
     /// Specify a custom PHP-UP directory
     #[arg(long = "phpup-dir", env = "PHPUP_DIR")]
     base_dir: Option<PathBuf>,
 
+    /// Specify a custom config file [default: $PHPUP_DIR/config.toml]
+    #[arg(long, env = "PHPUP_CONFIG_FILE")]
+    config_file: Option<PathBuf>,
+
+    /// Specify mirrors of the PHP source distributions in order of preference
+    /// [default: https://www.php.net/distributions]
+    #[arg(long = "mirror", env = "PHPUP_MIRRORS", value_delimiter = ',')]
+    mirrors: Vec<String>,
+
+    /// Specify mirrors of the old PHP source distributions in order of preference
+    /// [default: https://museum.php.net]
+    #[arg(
+        long = "museum-mirror",
+        env = "PHPUP_MUSEUM_MIRRORS",
+        value_delimiter = ','
+    )]
+    museum_mirrors: Vec<String>,
+
+    /// Specify mirrors of the pre-release (alpha, beta, RC) source archives in order of preference
+    /// [default: https://downloads.php.net]
+    #[arg(long = "qa-mirror", env = "PHPUP_QA_MIRRORS", value_delimiter = ',')]
+    qa_mirrors: Vec<String>,
+
+    /// Specify formats of the source archives in order of preference
+    /// [default: xz,gz,bz2]
+    #[arg(
+        long = "archive-format",
+        env = "PHPUP_ARCHIVE_FORMATS",
+        value_delimiter = ',',
+        value_parser = clap_enum_variants!(archive::Format)
+    )]
+    archive_formats: Vec<archive::Format>,
+
+    /// Specify the keyring of the PHP release managers to verify signatures of source archives
+    /// [default: $PHPUP_DIR/php-keyring.gpg]
+    #[arg(long, env = "PHPUP_KEYRING", value_name = "FILE")]
+    keyring: Option<PathBuf>,
+
+    /// Specify a custom URL of the release API
+    #[arg(long, env = "PHPUP_RELEASES_URL", hide = true)]
+    releases_url: Option<String>,
+
+    /// Specify a custom URL of the pre-release (alpha, beta, RC) API
+    #[arg(long, env = "PHPUP_QA_RELEASES_URL", hide = true)]
+    qa_releases_url: Option<String>,
+
+    /// Specify where to look up releases: php.net, a JSON index (URL or file) or a directory of source archives
+    /// [default: php.net]
+    #[arg(
+        long,
+        env = "PHPUP_RELEASE_SOURCE",
+        value_name = "php.net | URL | FILE | DIR"
+    )]
+    release_source: Option<String>,
+
+    /// Specify the support schedule of the branches in the format of endoflife.date (URL or file)
+    /// [default: the table of php.net built into PHP-UP]
+    #[arg(long, env = "PHPUP_SUPPORT_SCHEDULE", value_name = "URL | FILE")]
+    support_schedule: Option<String>,
+
+    /// Specify how long the fetched release lists are used without revalidation (e.g. `12h`, `7d`)
+    /// [default: 1d]
+    #[arg(long, env = "PHPUP_RELEASE_CACHE_TTL", value_name = "PERIOD")]
+    release_cache_ttl: Option<Period>,
+
+    /// Use only the cached release lists and source archives without network access
+    #[arg(long, env = "PHPUP_OFFLINE", global = true)]
+    offline: bool,
+
+    /// Specify the number of parallel jobs of `make`
+    /// [default: the CPUs and memory available in the cgroup]
+    #[arg(long, env = "PHPUP_MAKE_JOBS", global = true, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Run the build commands with the niceness (-20 to 19)
+    #[arg(
+        long,
+        env = "PHPUP_NICE",
+        global = true,
+        value_name = "LEVEL",
+        allow_negative_numbers = true,
+        value_parser = clap::value_parser!(i32).range(-20..=19)
+    )]
+    nice: Option<i32>,
+
+    /// Run the compilers through ccache when it's found in `PATH`
+    #[arg(long, env = "PHPUP_CCACHE", global = true)]
+    ccache: bool,
+
+    /// Build without checking the build dependencies beforehand
+    #[arg(long, env = "PHPUP_SKIP_PREFLIGHT", global = true)]
+    skip_preflight: bool,
+
+    /// Specify the HTTP client [default: native]
+    #[arg(long, env = "PHPUP_HTTP_BACKEND", value_parser = clap_enum_variants!(Backend))]
+    http_backend: Option<Backend>,
+
+    /// Specify the timeout in seconds to connect to a server [default: 30]
+    #[arg(long, env = "PHPUP_CONNECT_TIMEOUT", value_name = "SECONDS")]
+    connect_timeout: Option<u64>,
+
+    /// Specify the timeout in seconds to wait for data from a server [default: 60]
+    #[arg(long, env = "PHPUP_READ_TIMEOUT", value_name = "SECONDS")]
+    read_timeout: Option<u64>,
+
     /// Specify a custom symbolic link used for version switching
     #[arg(long, env = "PHPUP_MULTISHELL_PATH", hide = true)]
     multishell_path: Option<PathBuf>,
+
+    #[arg(skip)]
+    file: ConfigFile,
 }
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Not yet initialized; Need to run `eval \"$(phpup init)\"`")]
     NoMultiShellPath,
+
+    #[error(transparent)]
+    InvalidConfigFile(#[from] file::Error),
 }
 
 impl Config {
+    /// Loads the config file; settings already given by options or env vars are kept
+    pub fn load_file(mut self) -> Result<Self, Error> {
+        let filepath = self
+            .config_file
+            .clone()
+            .unwrap_or_else(|| self.base_dir().join(file::DEFAULT_CONFIG_FILE_NAME));
+        self.file = ConfigFile::read(filepath)?;
+        Ok(self)
+    }
+    pub fn mirrors(&self) -> Vec<&str> {
+        [&self.mirrors, &self.file.mirrors]
+            .into_iter()
+            .find(|mirrors| !mirrors.is_empty())
+            .map(|mirrors| mirrors.iter().map(String::as_str).collect())
+            .unwrap_or_else(|| vec![release::DEFAULT_MIRROR])
+    }
+    pub fn museum_mirrors(&self) -> Vec<&str> {
+        [&self.museum_mirrors, &self.file.museum_mirrors]
+            .into_iter()
+            .find(|mirrors| !mirrors.is_empty())
+            .map(|mirrors| mirrors.iter().map(String::as_str).collect())
+            .unwrap_or_else(|| vec![release::DEFAULT_MUSEUM_MIRROR])
+    }
+    pub fn qa_mirrors(&self) -> Vec<&str> {
+        [&self.qa_mirrors, &self.file.qa_mirrors]
+            .into_iter()
+            .find(|mirrors| !mirrors.is_empty())
+            .map(|mirrors| mirrors.iter().map(String::as_str).collect())
+            .unwrap_or_else(|| vec![release::DEFAULT_QA_MIRROR])
+    }
+    pub fn archive_formats(&self) -> &[archive::Format] {
+        [&self.archive_formats, &self.file.archive_formats]
+            .into_iter()
+            .find(|formats| !formats.is_empty())
+            .map_or(&archive::DEFAULT_PREFERENCE, |formats| formats.as_slice())
+    }
+    pub fn keyring(&self) -> PathBuf {
+        self.keyring
+            .clone()
+            .or_else(|| self.file.keyring.clone())
+            .unwrap_or_else(|| self.base_dir().join(signature::DEFAULT_KEYRING_FILE_NAME))
+    }
+    pub fn checksum_policy(&self) -> release::ChecksumPolicy {
+        self.file.checksum_policy.unwrap_or_default()
+    }
+    pub fn verify_signature(&self) -> signature::Policy {
+        self.file.verify_signature.unwrap_or_default()
+    }
+    pub fn releases_url(&self) -> &str {
+        self.releases_url
+            .as_deref()
+            .or(self.file.releases_url.as_deref())
+            .unwrap_or(release::DEFAULT_RELEASES_URL)
+    }
+    pub fn release_source<'a>(&'a self, http: &'a dyn Transport) -> Box<dyn ReleaseSource + 'a> {
+        match self
+            .release_source
+            .as_deref()
+            .or(self.file.release_source.as_deref())
+        {
+            None | Some("php.net") => Box::new(release::PhpNet::new(
+                http,
+                self.release_cache(),
+                self.releases_url(),
+                self.qa_releases_url(),
+            )),
+            Some(dir) if Path::new(dir).is_dir() => Box::new(release::Directory::new(dir)),
+            Some(index) => Box::new(release::JsonIndex::new(http, self.release_cache(), index)),
+        }
+    }
+    pub fn release_cache(&self) -> ReleaseCache {
+        ReleaseCache::new(self.release_cache_dir(), self.release_cache_ttl())
+    }
+    fn release_cache_dir(&self) -> PathBuf {
+        self.base_dir().join("cache").join("releases")
+    }
+    /// `None` in offline mode, in which the cache never expires
+    fn release_cache_ttl(&self) -> Option<chrono::Duration> {
+        let ttl = self
+            .release_cache_ttl
+            .or(self.file.release_cache_ttl)
+            .map_or_else(
+                || chrono::Duration::hours(release::cache::DEFAULT_TTL_HOURS),
+                |period| period.0,
+            );
+        (!self.offline).then_some(ttl)
+    }
+    /// Support schedule of `--support-schedule`, or the embedded one if it isn't given
+    pub fn try_support_schedule(&self) -> Result<Schedule, release::schedule::Error> {
+        self.load_support_schedule(&*self.http())
+    }
+    /// Same as `try_support_schedule`, but the embedded one is used if it can't be loaded
+    pub fn support_schedule(&self) -> Schedule {
+        self.try_support_schedule().unwrap_or_else(|e| {
+            eprintln!(
+                "{}: {}; Using the embedded schedule",
+                "warning".yellow().bold(),
+                e
+            );
+            Schedule::embedded()
+        })
+    }
+    /// Same as `support_schedule` without fetching it, using the cache of a URL if any
+    pub fn cached_support_schedule(&self) -> Schedule {
+        self.load_support_schedule(&http::Offline)
+            .unwrap_or_else(|_| Schedule::embedded())
+    }
+    fn load_support_schedule(
+        &self,
+        http: &dyn Transport,
+    ) -> Result<Schedule, release::schedule::Error> {
+        let configured = self
+            .support_schedule
+            .as_deref()
+            .or(self.file.support_schedule.as_deref());
+        match configured {
+            Some(location) => Schedule::load(
+                http,
+                location,
+                self.release_cache_dir(),
+                self.release_cache_ttl(),
+            ),
+            None => Ok(Schedule::embedded()),
+        }
+    }
+    pub fn http(&self) -> Box<dyn Transport> {
+        if self.offline {
+            return Box::new(http::Offline);
+        }
+        let backend = self
+            .http_backend
+            .or(self.file.http_backend)
+            .unwrap_or_default();
+        let timeouts = http::Timeouts {
+            connect: Duration::from_secs(
+                self.connect_timeout
+                    .or(self.file.connect_timeout)
+                    .unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            ),
+            read: Duration::from_secs(
+                self.read_timeout
+                    .or(self.file.read_timeout)
+                    .unwrap_or(DEFAULT_READ_TIMEOUT),
+            ),
+        };
+        http::client(backend, timeouts)
+    }
+    /// `None` to decide by the available resources
+    pub fn make_jobs(&self) -> Option<usize> {
+        self.jobs.or(self.file.jobs).filter(|&jobs| jobs > 0)
+    }
+    pub fn nice(&self) -> Option<i32> {
+        self.nice.or(self.file.nice)
+    }
+    pub fn ccache(&self) -> bool {
+        self.ccache || self.file.ccache.unwrap_or(false)
+    }
+    pub fn profile(&self, name: &str) -> Option<&file::Profile> {
+        self.file.profiles.get(name)
+    }
+    pub fn skip_preflight(&self) -> bool {
+        self.skip_preflight
+    }
     pub fn multishell_path(&self) -> Result<&Path, Error> {
         self.multishell_path
             .as_deref()
             .ok_or(Error::NoMultiShellPath)
     }
+    pub fn qa_releases_url(&self) -> &str {
+        self.qa_releases_url
+            .as_deref()
+            .or(self.file.qa_releases_url.as_deref())
+            .unwrap_or(release::qa::DEFAULT_URL)
+    }
     pub fn base_dir(&self) -> PathBuf {
         if let Some(base_dir) = self.base_dir.as_ref() {
             base_dir.clone()
@@ -40,6 +335,19 @@ impl Config {
             .unwrap_or_else(|_| panic!("Can't create version dirctory: {:?}", versions_dir));
         versions_dir
     }
+    pub fn cache_dir(&self) -> PathBuf {
+        let cache_dir = self.base_dir().join("cache").join("downloads");
+        fs::create_dir_all(&cache_dir)
+            .unwrap_or_else(|_| panic!("Can't create cache dirctory: {:?}", cache_dir));
+        cache_dir
+    }
+    /// Build logs, kept in a directory for each install name
+    pub fn logs_dir(&self) -> PathBuf {
+        let logs_dir = self.base_dir().join("logs");
+        fs::create_dir_all(&logs_dir)
+            .unwrap_or_else(|_| panic!("Can't create log dirctory: {:?}", logs_dir));
+        logs_dir
+    }
     pub fn aliases_dir(&self) -> PathBuf {
         let aliases_dir = self.base_dir().join("aliases");
         fs::create_dir_all(&aliases_dir)
@@ -52,4 +360,22 @@ impl Config {
         self.base_dir = Some(PathBuf::from(base_dir.as_ref()));
         self
     }
+
+    #[cfg(test)]
+    pub fn with_skip_preflight(mut self) -> Self {
+        self.skip_preflight = true;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_support_schedule(mut self, support_schedule: impl Into<String>) -> Self {
+        self.support_schedule = Some(support_schedule.into());
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_release_source(mut self, release_source: impl Into<String>) -> Self {
+        self.release_source = Some(release_source.into());
+        self
+    }
 }
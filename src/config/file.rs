@@ -0,0 +1,162 @@
+use crate::archive;
+use crate::cache::Period;
+use crate::http::Backend;
+use crate::release::ChecksumPolicy;
+use crate::signature;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub const DEFAULT_CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Settings read from `config.toml` in the PHP-UP directory.
+/// Command line options and environment variables take precedence over them.
+///
+/// ```toml
+/// mirrors = ["https://proxy.example.com/php/distributions"]
+/// museum_mirrors = ["https://proxy.example.com/php/museum"]
+/// qa_mirrors = ["https://proxy.example.com/php/qa"]
+/// archive_formats = ["gz"]
+/// verify_signature = "required"
+/// checksum_policy = "sha256-only"
+/// releases_url = "https://proxy.example.com/php/releases/index.php"
+/// http_backend = "curl"
+/// connect_timeout = 10
+/// ccache = true
+///
+/// [profiles.clang]
+/// cc = "clang"
+/// cxx = "clang++"
+/// cflags = "-O2 -march=native"
+/// ```
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    #[serde(default)]
+    pub museum_mirrors: Vec<String>,
+    #[serde(default)]
+    pub qa_mirrors: Vec<String>,
+    #[serde(default)]
+    pub archive_formats: Vec<archive::Format>,
+    pub keyring: Option<PathBuf>,
+    pub verify_signature: Option<signature::Policy>,
+    pub checksum_policy: Option<ChecksumPolicy>,
+    pub releases_url: Option<String>,
+    pub qa_releases_url: Option<String>,
+    pub release_source: Option<String>,
+    pub release_cache_ttl: Option<Period>,
+    pub support_schedule: Option<String>,
+    pub jobs: Option<usize>,
+    pub nice: Option<i32>,
+    pub ccache: Option<bool>,
+    /// Compilers and flags chosen by `install --profile <name>`
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    pub http_backend: Option<Backend>,
+    pub connect_timeout: Option<u64>,
+    pub read_timeout: Option<u64>,
+}
+
+/// Compilers and flags to build PHP with
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub cc: Option<String>,
+    pub cxx: Option<String>,
+    pub cflags: Option<String>,
+    pub ldflags: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't read config file {filepath}: {source}")]
+    FailedRead {
+        filepath: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Can't parse config file {filepath}: {source}")]
+    FailedParse {
+        filepath: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+}
+
+impl ConfigFile {
+    /// Returns the default settings if `filepath` doesn't exist
+    pub fn read(filepath: impl AsRef<Path>) -> Result<Self, Error> {
+        let filepath = filepath.as_ref();
+        if !filepath.is_file() {
+            return Ok(Self::default());
+        }
+        let toml = fs::read_to_string(filepath).map_err(|source| Error::FailedRead {
+            filepath: filepath.to_owned(),
+            source,
+        })?;
+        toml::from_str(&toml).map_err(|source| Error::FailedParse {
+            filepath: filepath.to_owned(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn read() {
+        let dir = tempfile::tempdir().unwrap();
+        let filepath = dir.path().join(DEFAULT_CONFIG_FILE_NAME);
+        assert!(ConfigFile::read(&filepath).unwrap().mirrors.is_empty());
+
+        fs::File::create(&filepath)
+            .unwrap()
+            .write_all(
+                br#"
+                    mirrors = ["https://a.example.com/distributions", "https://b.example.com"]
+                    releases_url = "https://a.example.com/releases/index.php"
+                    http_backend = "curl"
+                    release_cache_ttl = "12h"
+
+                    [profiles.debug]
+                    cflags = "-O0 -g"
+                "#,
+            )
+            .unwrap();
+        let config_file = ConfigFile::read(&filepath).unwrap();
+        assert_eq!(config_file.mirrors.len(), 2);
+        assert_eq!(
+            config_file.releases_url.as_deref(),
+            Some("https://a.example.com/releases/index.php")
+        );
+        assert_eq!(config_file.http_backend, Some(Backend::Curl));
+        assert_eq!(
+            config_file.release_cache_ttl,
+            Some(Period(chrono::Duration::hours(12)))
+        );
+        assert_eq!(
+            config_file.profiles["debug"],
+            Profile {
+                cflags: Some("-O0 -g".to_owned()),
+                ..Default::default()
+            }
+        );
+
+        fs::File::create(&filepath)
+            .unwrap()
+            .write_all(b"mirror = 1")
+            .unwrap();
+        assert!(matches!(
+            ConfigFile::read(&filepath),
+            Err(Error::FailedParse { .. })
+        ));
+    }
+}
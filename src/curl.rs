@@ -1,20 +1,6 @@
-use std::process::ChildStderr;
-use std::process::ChildStdout;
-use std::process::Command;
-use std::process::Stdio;
-use thiserror::Error;
-
-#[derive(Error, Debug)]
-pub enum Error {
-    #[error("Can't execute `{command}` because {source}")]
-    FailedExecute {
-        command: String,
-        #[source]
-        source: std::io::Error,
-    },
-    #[error("Failed to execute `{0}` because {1}")]
-    ExitFailed(String, String),
-}
+use crate::http::{Conditional, Error, Header, Timeouts, Transport, Validators};
+use std::io::Read;
+use std::process::{Child, ChildStdout, Command, Stdio};
 
 const CURL_PATH: &str = if cfg!(target_os = "windows") {
     "curl.exe"
@@ -22,76 +8,269 @@ const CURL_PATH: &str = if cfg!(target_os = "windows") {
     "curl"
 };
 
-pub struct Header {
-    pub content_length: Option<usize>,
+/// HTTP transport by the `curl` command
+pub struct Curl {
+    timeouts: Timeouts,
 }
 
-pub fn get_as_slice(url: &str) -> Result<Vec<u8>, Error> {
-    let command = [CURL_PATH, url, "-sS"];
-    let output = Command::new(command[0])
-        .args(&command[1..])
-        .output()
-        .map_err(|source| Error::FailedExecute {
-            command: command.join(" "),
-            source,
-        })?;
-    if output.status.success() {
-        Ok(output.stdout)
-    } else {
-        let err_msg = String::from_utf8(output.stderr).unwrap();
-        Err(Error::ExitFailed(command.join(" "), err_msg))
+impl Curl {
+    pub fn new(timeouts: Timeouts) -> Self {
+        Self { timeouts }
+    }
+    fn command(&self, url: &str, args: &[&str]) -> Vec<String> {
+        let connect_timeout = self.timeouts.connect.as_secs().max(1).to_string();
+        let read_timeout = self.timeouts.read.as_secs().max(1).to_string();
+        [CURL_PATH, url, "-sSL", "--fail"]
+            .into_iter()
+            .chain(["--connect-timeout", &connect_timeout])
+            // abort if nothing is received during the read timeout
+            .chain(["--speed-limit", "1", "--speed-time", &read_timeout])
+            .chain(args.iter().copied())
+            .map(str::to_owned)
+            .collect()
+    }
+    fn output(&self, url: &str, args: &[&str]) -> Result<Vec<u8>, Error> {
+        let command = self.command(url, args);
+        let output = Command::new(&command[0])
+            .args(&command[1..])
+            .output()
+            .map_err(|source| Error::FailedExecute {
+                command: command.join(" "),
+                source,
+            })?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(map_exit_code(
+                output.status.code(),
+                &String::from_utf8_lossy(&output.stderr),
+                url,
+            ))
+        }
     }
 }
 
-pub fn get_as_reader(url: &str) -> Result<(ChildStdout, ChildStderr), Error> {
-    let command = [CURL_PATH, url, "-sS"];
-    let process = Command::new(command[0])
-        .args(&command[1..])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|source| Error::FailedExecute {
-            command: command.join(" "),
-            source,
-        })?;
-    Ok((process.stdout.unwrap(), process.stderr.unwrap()))
+// https://curl.se/libcurl/c/libcurl-errors.html
+fn map_exit_code(code: Option<i32>, stderr: &str, url: &str) -> Error {
+    let url = url.to_owned();
+    // "curl: (22) The requested URL returned error: 404"
+    let message = stderr.trim().trim_start_matches("curl: ");
+    let message = match message.split_once(") ") {
+        Some((code, rest)) if code.starts_with('(') => rest,
+        _ => message,
+    }
+    .to_owned();
+    match code {
+        Some(5 | 6) => Error::Dns { url, message },
+        Some(7) => Error::Connect { url, message },
+        Some(28) => Error::Timeout { url },
+        Some(35 | 51 | 53 | 54 | 58 | 59 | 60 | 64 | 66 | 77 | 80 | 82 | 83 | 90 | 91) => {
+            Error::Tls { url, message }
+        }
+        Some(22) => match message
+            .rsplit(": ")
+            .next()
+            .and_then(|status| status.split_whitespace().next())
+            .and_then(|status| status.parse().ok())
+        {
+            Some(status) => Error::Status { url, status },
+            None => Error::Other { url, message },
+        },
+        Some(33) => Error::RangeNotSupported { url },
+        Some(18 | 52 | 55 | 56 | 92) => Error::Interrupted { url, message },
+        _ => Error::Other { url, message },
+    }
 }
 
-pub fn get_header(url: &str) -> Result<Header, Error> {
-    let command = [CURL_PATH, url, "-sSI"];
-    let output = Command::new(command[0])
-        .args(&command[1..])
-        .output()
-        .map_err(|source| Error::FailedExecute {
-            command: command.join(" "),
-            source,
-        })?;
-    let output = if output.status.success() {
-        String::from_utf8(output.stdout).unwrap()
-    } else {
-        return Err(Error::ExitFailed(
-            command.join(" "),
-            String::from_utf8(output.stderr).unwrap(),
-        ));
-    };
+impl Transport for Curl {
+    fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        self.output(url, &[])
+    }
+    fn head(&self, url: &str) -> Result<Header, Error> {
+        let output = self.output(url, &["-I"])?;
+        Ok(parse_header(&String::from_utf8_lossy(&output)))
+    }
+    fn get_if_modified(&self, url: &str, validators: &Validators) -> Result<Conditional, Error> {
+        let header_file =
+            tempfile::NamedTempFile::new().map_err(|source| Error::FailedExecute {
+                command: CURL_PATH.to_owned(),
+                source,
+            })?;
+        let header_path = header_file.path().to_string_lossy().into_owned();
+        let if_none_match = validators
+            .etag
+            .as_ref()
+            .map(|etag| format!("If-None-Match: {}", etag));
+        let if_modified_since = validators
+            .last_modified
+            .as_ref()
+            .map(|last_modified| format!("If-Modified-Since: {}", last_modified));
+        let mut args = vec!["-D", &header_path];
+        for header in [&if_none_match, &if_modified_since].into_iter().flatten() {
+            args.extend(["-H", header]);
+        }
+        let body = self.output(url, &args)?;
+
+        let header = std::fs::read_to_string(header_file.path()).unwrap_or_default();
+        let (status, fields) = last_response(&header);
+        if status == Some(304) {
+            return Ok(Conditional::NotModified);
+        }
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.to_string())
+        };
+        let validators = Validators {
+            etag: field("etag"),
+            last_modified: field("last-modified"),
+        };
+        Ok(Conditional::Modified { body, validators })
+    }
+    fn get_from(&self, url: &str, offset: u64) -> Result<Box<dyn Read + Send>, Error> {
+        let offset = offset.to_string();
+        let args = if offset != "0" {
+            // fails with exit code 33 if the server doesn't support byte ranges
+            vec!["-C", &offset]
+        } else {
+            vec![]
+        };
+        let command = self.command(url, &args);
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|source| Error::FailedExecute {
+                command: command.join(" "),
+                source,
+            })?;
+        Ok(Box::new(Body {
+            stdout: child.stdout.take().unwrap(),
+            child: Some(child),
+            url: url.to_owned(),
+        }))
+    }
+}
+
+/// Stdout of `curl` which checks the exit status at the end
+struct Body {
+    stdout: ChildStdout,
+    child: Option<Child>,
+    url: String,
+}
 
-    let mut lines = output.lines();
-    let content_length = lines
-        .find(|line| line.starts_with("content-length"))
-        .and_then(|line| line.split(": ").last())
-        .and_then(|length| length.parse::<usize>().ok());
-    Ok(Header { content_length })
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.stdout.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            if let Some(child) = self.child.take() {
+                let output = child.wait_with_output()?;
+                if !output.status.success() {
+                    return Err(std::io::Error::other(map_exit_code(
+                        output.status.code(),
+                        &String::from_utf8_lossy(&output.stderr),
+                        &self.url,
+                    )));
+                }
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Stops `curl` if the body is dropped before the end, e.g. by an aborted download
+impl Drop for Body {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+/// Returns the status code and the header fields of the last response,
+/// since headers of all redirects are printed
+fn last_response(output: &str) -> (Option<u16>, Vec<(&str, &str)>) {
+    let mut lines = output.lines().rev().collect::<Vec<_>>();
+    let status_line = lines.iter().position(|line| line.starts_with("HTTP/"));
+    let status = status_line
+        .and_then(|i| lines[i].split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok());
+    lines.truncate(status_line.unwrap_or(lines.len()));
+    let fields = lines
+        .into_iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    (status, fields)
+}
+
+fn parse_header(output: &str) -> Header {
+    let (_, fields) = last_response(output);
+    let content_length = fields
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, length)| length.parse::<u64>().ok());
+    Header { content_length }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
-    fn test() {
-        let output = get_as_slice("http://examsssssssple.com/");
-        match output {
-            Ok(v) => println!("{:?}", v),
-            Err(e) => println!("{}", e),
-        }
+    fn parse_redirected_header() {
+        let output = "HTTP/1.1 302 Found\r\nContent-Length: 0\r\nLocation: https://example.com/php.tar.gz\r\n\r\nHTTP/2 200\r\ncontent-length: 12345\r\n\r\n";
+        assert_eq!(parse_header(output).content_length, Some(12345));
+        let output = "HTTP/1.1 302 Found\r\nContent-Length: 0\r\n\r\nHTTP/2 200\r\n\r\n";
+        assert_eq!(parse_header(output).content_length, None);
+    }
+    #[test]
+    fn map_errors() {
+        let url = "https://www.php.net/";
+        assert!(matches!(
+            map_exit_code(
+                Some(22),
+                "curl: (22) The requested URL returned error: 503\n",
+                url
+            ),
+            Error::Status { status: 503, .. }
+        ));
+        assert!(matches!(
+            map_exit_code(
+                Some(6),
+                "curl: (6) Could not resolve host: www.php.net\n",
+                url
+            ),
+            Error::Dns { .. }
+        ));
+        assert!(matches!(
+            map_exit_code(Some(60), "curl: (60) SSL certificate problem\n", url),
+            Error::Tls { .. }
+        ));
+    }
+
+    #[test]
+    fn stop_curl_when_dropped() {
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let pid = child.id().to_string();
+        let body = Body {
+            stdout: child.stdout.take().unwrap(),
+            child: Some(child),
+            url: "https://www.php.net/".to_owned(),
+        };
+        drop(body);
+        // neither running nor left as a zombie
+        let alive = Command::new("kill")
+            .args(["-0", &pid])
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(!alive.success());
     }
 }
@@ -0,0 +1,369 @@
+use serde::{Deserialize, Serialize};
+use serde_with::DeserializeFromStr;
+use std::io::Read;
+use std::str::FromStr;
+use std::time::Duration;
+use strum::EnumVariantNames;
+use thiserror::Error;
+
+pub mod native;
+pub mod proxy;
+
+pub use crate::curl::Curl;
+pub use native::Native;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't resolve the host of {url}: {message}")]
+    Dns { url: String, message: String },
+
+    #[error("Can't connect to {url}: {message}")]
+    Connect { url: String, message: String },
+
+    #[error("TLS handshake with {url} failed: {message}")]
+    Tls { url: String, message: String },
+
+    #[error("Timed out while requesting {url}")]
+    Timeout { url: String },
+
+    #[error("{url} responded with HTTP status {status}")]
+    Status { url: String, status: u16 },
+
+    #[error("{url} doesn't support resuming a download")]
+    RangeNotSupported { url: String },
+
+    #[error("Connection to {url} was interrupted: {message}")]
+    Interrupted { url: String, message: String },
+
+    #[error("Request to {url} failed: {message}")]
+    Other { url: String, message: String },
+
+    #[error("{url} isn't cached; Can't request it in offline mode")]
+    Offline { url: String },
+
+    #[error("Can't execute `{command}` because {source}")]
+    FailedExecute {
+        command: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl Error {
+    /// Returns `true` if the request may succeed by retrying
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Connect { .. } | Error::Timeout { .. } | Error::Interrupted { .. } => true,
+            Error::Status { status, .. } => *status >= 500 || *status == 429,
+            _ => false,
+        }
+    }
+}
+
+pub struct Header {
+    pub content_length: Option<u64>,
+}
+
+/// Validators of a stored response for a conditional request
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+pub enum Conditional {
+    NotModified,
+    Modified {
+        body: Vec<u8>,
+        validators: Validators,
+    },
+}
+
+/// A way to send HTTP requests
+pub trait Transport: Send + Sync {
+    fn get(&self, url: &str) -> Result<Vec<u8>, Error>;
+    fn head(&self, url: &str) -> Result<Header, Error>;
+    /// Sends `If-None-Match` and `If-Modified-Since` built from `validators`
+    fn get_if_modified(&self, url: &str, validators: &Validators) -> Result<Conditional, Error>;
+    /// Returns the body from `offset` bytes.
+    /// Reading it fails with an [`Error`] wrapped in [`std::io::Error`] if the transfer is broken.
+    fn get_from(&self, url: &str, offset: u64) -> Result<Box<dyn Read + Send>, Error>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumVariantNames, DeserializeFromStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum Backend {
+    /// In-process HTTP client
+    #[default]
+    Native,
+    /// `curl` command
+    Curl,
+}
+
+#[derive(Error, Debug)]
+pub enum ParseBackendError {
+    #[error("Unknown HTTP backend: {0}")]
+    UnknownBackend(String),
+}
+
+impl FromStr for Backend {
+    type Err = ParseBackendError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(Backend::Native),
+            "curl" => Ok(Backend::Curl),
+            _ => Err(ParseBackendError::UnknownBackend(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    pub connect: Duration,
+    pub read: Duration,
+}
+
+/// Transport refusing every request, used by `--offline`
+pub struct Offline;
+
+impl Transport for Offline {
+    fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        Err(Error::Offline {
+            url: url.to_owned(),
+        })
+    }
+    fn head(&self, url: &str) -> Result<Header, Error> {
+        Err(Error::Offline {
+            url: url.to_owned(),
+        })
+    }
+    fn get_if_modified(&self, url: &str, _: &Validators) -> Result<Conditional, Error> {
+        Err(Error::Offline {
+            url: url.to_owned(),
+        })
+    }
+    fn get_from(&self, url: &str, _: u64) -> Result<Box<dyn Read + Send>, Error> {
+        Err(Error::Offline {
+            url: url.to_owned(),
+        })
+    }
+}
+
+pub fn client(backend: Backend, timeouts: Timeouts) -> Box<dyn Transport> {
+    match backend {
+        Backend::Native => Box::new(Native::new(timeouts)),
+        Backend::Curl => Box::new(Curl::new(timeouts)),
+    }
+}
+
+/// Recovers an [`Error`] from an I/O error raised while reading a body
+pub fn from_io_error(e: std::io::Error, url: &str) -> Error {
+    match e.get_ref().and_then(|inner| inner.downcast_ref::<Error>()) {
+        Some(_) => *e.into_inner().unwrap().downcast::<Error>().unwrap(),
+        None if e.kind() == std::io::ErrorKind::TimedOut => Error::Timeout {
+            url: url.to_owned(),
+        },
+        None => Error::Interrupted {
+            url: url.to_owned(),
+            message: e.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Minimal HTTP server standing in for a mirror.
+    /// It honors `Range: bytes=<n>-` and drops the first `drops` GET requests halfway through the body.
+    pub struct StandIn {
+        pub url: String,
+        pub requests: Arc<AtomicUsize>,
+        /// GET requests answered by `304 Not Modified`
+        pub not_modified: Arc<AtomicUsize>,
+    }
+
+    impl StandIn {
+        pub fn serve(body: Vec<u8>, drops: usize) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!(
+                "http://{}/php-8.2.10.tar.gz",
+                listener.local_addr().unwrap()
+            );
+            let requests = Arc::new(AtomicUsize::new(0));
+            let not_modified = Arc::new(AtomicUsize::new(0));
+            let (counter, not_modified_counter) =
+                (Arc::clone(&requests), Arc::clone(&not_modified));
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    respond(stream, &body, drops, &counter, &not_modified_counter);
+                }
+            });
+            Self {
+                url,
+                requests,
+                not_modified,
+            }
+        }
+    }
+
+    const ETAG: &str = "\"stand-in\"";
+
+    /// Counts GET requests before responding, so that a client never sees a response not counted yet
+    fn respond(
+        mut stream: TcpStream,
+        body: &[u8],
+        drops: usize,
+        requests: &AtomicUsize,
+        not_modified: &AtomicUsize,
+    ) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut offset = 0;
+        let mut if_none_match = None;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            let line = line.to_lowercase();
+            if let Some(range) = line.strip_prefix("range: bytes=") {
+                offset = range.trim().trim_end_matches('-').parse().unwrap();
+            }
+            if let Some(etag) = line.strip_prefix("if-none-match:") {
+                if_none_match = Some(etag.trim().to_owned());
+            }
+        }
+        if request_line.contains("/not-found") {
+            write!(
+                stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+            )
+            .unwrap();
+            return;
+        }
+        if if_none_match.as_deref() == Some(ETAG) {
+            requests.fetch_add(1, Ordering::SeqCst);
+            not_modified.fetch_add(1, Ordering::SeqCst);
+            write!(
+                stream,
+                "HTTP/1.1 304 Not Modified\r\nETag: {}\r\n\r\n",
+                ETAG
+            )
+            .unwrap();
+            return;
+        }
+        let is_head = request_line.starts_with("HEAD");
+        let drop_halfway = !is_head && requests.fetch_add(1, Ordering::SeqCst) < drops;
+        let (status, content_range) = if offset > 0 {
+            (
+                "206 Partial Content",
+                format!(
+                    "Content-Range: bytes {}-{}/{}\r\n",
+                    offset,
+                    body.len() - 1,
+                    body.len()
+                ),
+            )
+        } else {
+            ("200 OK", String::new())
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}ETag: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n",
+            status,
+            body.len() - offset,
+            content_range,
+            ETAG
+        )
+        .unwrap();
+        if !is_head {
+            let body = &body[offset..];
+            let end = if drop_halfway {
+                body.len() / 2
+            } else {
+                body.len()
+            };
+            stream.write_all(&body[..end]).ok();
+        }
+    }
+
+    pub fn backends() -> Vec<Box<dyn Transport>> {
+        let timeouts = Timeouts {
+            connect: Duration::from_secs(5),
+            read: Duration::from_secs(5),
+        };
+        vec![
+            client(Backend::Native, timeouts),
+            client(Backend::Curl, timeouts),
+        ]
+    }
+
+    #[test]
+    fn get_and_head() {
+        let server = StandIn::serve(b"hello world".to_vec(), 0);
+        for http in backends() {
+            assert_eq!(http.get(&server.url).unwrap(), b"hello world");
+            assert_eq!(http.head(&server.url).unwrap().content_length, Some(11));
+
+            let mut body = String::new();
+            http.get_from(&server.url, 6)
+                .unwrap()
+                .read_to_string(&mut body)
+                .unwrap();
+            assert_eq!(body, "world");
+
+            let validators = match http.get_if_modified(&server.url, &Validators::default()) {
+                Ok(Conditional::Modified { body, validators }) => {
+                    assert_eq!(body, b"hello world");
+                    validators
+                }
+                _ => panic!("expected the body"),
+            };
+            assert_eq!(validators.etag.as_deref(), Some(ETAG));
+            assert!(matches!(
+                http.get_if_modified(&server.url, &validators),
+                Ok(Conditional::NotModified)
+            ));
+        }
+    }
+
+    #[test]
+    fn typed_errors() {
+        let server = StandIn::serve(Vec::new(), 0);
+        let not_found = server.url.replace("php-8.2.10.tar.gz", "not-found");
+        for http in backends() {
+            assert!(matches!(
+                http.get(&not_found),
+                Err(Error::Status { status: 404, .. })
+            ));
+            assert!(matches!(
+                http.get("http://phpup.invalid/"),
+                Err(Error::Dns { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn interrupted_body() {
+        let server = StandIn::serve(vec![0; 100_000], usize::MAX);
+        for http in backends() {
+            let mut body = Vec::new();
+            let e = http
+                .get_from(&server.url, 0)
+                .unwrap()
+                .read_to_end(&mut body)
+                .unwrap_err();
+            assert!(from_io_error(e, &server.url).is_transient());
+        }
+    }
+}
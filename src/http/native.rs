@@ -0,0 +1,131 @@
+use super::{proxy, Conditional, Error, Header, Timeouts, Transport, Validators};
+use std::io::Read;
+
+const MAX_REDIRECTS: u32 = 10;
+
+/// In-process HTTP client with TLS, redirects and proxies from the environment
+pub struct Native {
+    timeouts: Timeouts,
+}
+
+impl Native {
+    pub fn new(timeouts: Timeouts) -> Self {
+        Self { timeouts }
+    }
+    fn agent(&self, url: &str) -> Result<ureq::Agent, Error> {
+        let mut builder = ureq::AgentBuilder::new()
+            .timeout_connect(self.timeouts.connect)
+            .timeout_read(self.timeouts.read)
+            .redirects(MAX_REDIRECTS)
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ));
+        if let Some(proxy) = proxy::for_url(url) {
+            let proxy = ureq::Proxy::new(&proxy).map_err(|e| Error::Other {
+                url: url.to_owned(),
+                message: format!("invalid proxy '{}': {}", proxy, e),
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder.build())
+    }
+}
+
+fn map_error(e: ureq::Error, url: &str) -> Error {
+    use ureq::ErrorKind;
+
+    let url = url.to_owned();
+    let transport = match e {
+        ureq::Error::Status(status, _) => return Error::Status { url, status },
+        ureq::Error::Transport(transport) => transport,
+    };
+    let message = transport.to_string();
+    let timed_out = std::error::Error::source(&transport)
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .is_some_and(|e| {
+            matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+            )
+        });
+    match transport.kind() {
+        ErrorKind::Dns => Error::Dns { url, message },
+        ErrorKind::ConnectionFailed
+            if transport
+                .message()
+                .is_some_and(|message| message.starts_with("tls")) =>
+        {
+            Error::Tls { url, message }
+        }
+        _ if timed_out => Error::Timeout { url },
+        ErrorKind::ConnectionFailed | ErrorKind::ProxyConnect => Error::Connect { url, message },
+        ErrorKind::Io => Error::Interrupted { url, message },
+        _ => Error::Other { url, message },
+    }
+}
+
+impl Transport for Native {
+    fn get(&self, url: &str) -> Result<Vec<u8>, Error> {
+        let response = self
+            .agent(url)?
+            .get(url)
+            .call()
+            .map_err(|e| map_error(e, url))?;
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|e| super::from_io_error(e, url))?;
+        Ok(body)
+    }
+    fn head(&self, url: &str) -> Result<Header, Error> {
+        let response = self
+            .agent(url)?
+            .head(url)
+            .call()
+            .map_err(|e| map_error(e, url))?;
+        Ok(Header {
+            content_length: response
+                .header("content-length")
+                .and_then(|length| length.parse().ok()),
+        })
+    }
+    fn get_if_modified(&self, url: &str, validators: &Validators) -> Result<Conditional, Error> {
+        let mut request = self.agent(url)?.get(url);
+        if let Some(etag) = &validators.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+        let response = request.call().map_err(|e| map_error(e, url))?;
+        if response.status() == 304 {
+            return Ok(Conditional::NotModified);
+        }
+        let validators = Validators {
+            etag: response.header("etag").map(ToOwned::to_owned),
+            last_modified: response.header("last-modified").map(ToOwned::to_owned),
+        };
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|e| super::from_io_error(e, url))?;
+        Ok(Conditional::Modified { body, validators })
+    }
+    fn get_from(&self, url: &str, offset: u64) -> Result<Box<dyn Read + Send>, Error> {
+        let mut request = self.agent(url)?.get(url);
+        if offset > 0 {
+            request = request.set("Range", &format!("bytes={}-", offset));
+        }
+        let response = request.call().map_err(|e| map_error(e, url))?;
+        if offset > 0 && response.status() != 206 {
+            return Err(Error::RangeNotSupported {
+                url: url.to_owned(),
+            });
+        }
+        Ok(Box::new(response.into_reader()))
+    }
+}
@@ -0,0 +1,103 @@
+use std::env;
+
+/// Returns the proxy to use for `url` following the conventions of curl:
+/// `https_proxy`, `http_proxy`, `all_proxy` (or their upper case) and `no_proxy`
+pub fn for_url(url: &str) -> Option<String> {
+    for_url_with(url, |name| {
+        env::var(name)
+            .ok()
+            .or_else(|| env::var(name.to_uppercase()).ok())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+fn for_url_with(url: &str, var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let host = host(rest);
+
+    if let Some(no_proxy) = var("no_proxy") {
+        if no_proxy
+            .split(',')
+            .map(str::trim)
+            .any(|pattern| matches_no_proxy(pattern, host))
+        {
+            return None;
+        }
+    }
+    match scheme {
+        "https" => var("https_proxy"),
+        "http" => var("http_proxy"),
+        _ => None,
+    }
+    .or_else(|| var("all_proxy"))
+}
+
+fn host(authority_and_path: &str) -> &str {
+    let authority = authority_and_path
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host_port = authority.rsplit('@').next().unwrap_or(authority);
+    if let Some(ipv6) = host_port.strip_prefix('[') {
+        ipv6.split(']').next().unwrap_or(ipv6)
+    } else {
+        host_port.split(':').next().unwrap_or(host_port)
+    }
+}
+
+fn matches_no_proxy(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim_start_matches('.');
+    pattern == "*"
+        || (!pattern.is_empty()
+            && (host.eq_ignore_ascii_case(pattern)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", pattern.to_ascii_lowercase()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn select_by_scheme() {
+        let vars = env(&[
+            ("https_proxy", "http://proxy.internal:3128"),
+            ("all_proxy", "socks5://proxy.internal:1080"),
+        ]);
+        assert_eq!(
+            for_url_with("https://www.php.net/distributions/", &vars).as_deref(),
+            Some("http://proxy.internal:3128")
+        );
+        assert_eq!(
+            for_url_with("http://museum.php.net/", &vars).as_deref(),
+            Some("socks5://proxy.internal:1080")
+        );
+        assert_eq!(for_url_with("https://www.php.net/", env(&[])), None);
+    }
+
+    #[test]
+    fn no_proxy() {
+        let vars = env(&[
+            ("https_proxy", "http://proxy.internal:3128"),
+            ("no_proxy", "localhost, .php.net,10.0.0.1"),
+        ]);
+        assert_eq!(for_url_with("https://www.php.net/", &vars), None);
+        assert_eq!(for_url_with("https://php.net/", &vars), None);
+        assert_eq!(for_url_with("https://user@localhost:8080/a", &vars), None);
+        assert_eq!(for_url_with("https://10.0.0.1/", &vars), None);
+        assert!(for_url_with("https://notphp.net/", &vars).is_some());
+
+        let vars = env(&[("https_proxy", "http://proxy:3128"), ("no_proxy", "*")]);
+        assert_eq!(for_url_with("https://www.php.net/", &vars), None);
+    }
+}
@@ -1,9 +1,16 @@
+pub mod archive;
+pub mod cache;
 pub mod cli;
 pub mod commands;
 pub mod config;
 pub mod curl;
 pub mod decorized;
+pub mod http;
+pub mod manifest;
+pub mod parallel;
 pub mod release;
 pub mod shell;
+pub mod signature;
 pub mod symlink;
+pub mod variant;
 pub mod version;
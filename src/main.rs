@@ -1,7 +1,11 @@
-use clap::Parser;
+use colored::Colorize;
 use phpup::cli::Cli;
 
 fn main() {
-    let Cli { config, subcmd } = Cli::parse();
+    let Cli { config, subcmd } = Cli::parse_args();
+    let config = config.load_file().unwrap_or_else(|e| {
+        eprintln!("{}: {}", "error".red().bold(), e);
+        std::process::exit(1);
+    });
     subcmd.apply(config);
 }
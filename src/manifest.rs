@@ -0,0 +1,306 @@
+use crate::release::{ChecksumPolicy, Hash};
+use crate::variant::Variant;
+use crate::version::{Local, Version};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const MANIFEST_FILE_NAME: &str = ".phpup-install.json";
+
+/// Build information recorded in each install directory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    pub version: Version,
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+    #[serde(default)]
+    pub configure_opts: Vec<String>,
+    /// URL or path of the source archive or directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    /// Name of the install directory instead of the version, e.g. `8.4-dev`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitSource>,
+    /// When the build finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub built_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Toolchain::is_empty")]
+    pub toolchain: Toolchain,
+    /// First line of `cc --version`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<String>,
+    /// Seconds the build took, to estimate later builds of the same minor version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_seconds: Option<u64>,
+    /// Version of PHP-UP that built it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phpup_version: Option<String>,
+}
+
+/// How the source archive was verified before the build
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub checksum_policy: ChecksumPolicy,
+    /// `None` if the release has no checksum and the policy allowed it
+    pub checksum: Option<Hash>,
+    /// The checksum was given by `--checksum` instead of the release source
+    #[serde(default)]
+    pub user_supplied_checksum: bool,
+    /// Signer of the verified OpenPGP signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+}
+
+/// Git checkout the version was built from, to rebuild the same ref later
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+    /// URL or absolute path of the repository
+    pub repository: String,
+    /// Branch, tag or commit given by `--ref`, or the default branch
+    pub reference: String,
+    pub commit: String,
+}
+
+/// Compilers and flags given by `--profile`, `--cc`, `--cxx`, `--cflags` and `--ldflags`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Toolchain {
+    /// Profile of the config file they were taken from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cc: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cxx: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cflags: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ldflags: Option<String>,
+    /// The compilers were run through ccache
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ccache: bool,
+}
+
+impl Toolchain {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    /// Environment variables of the build, with the compilers wrapped by ccache if it's used
+    pub fn envs(&self) -> Vec<(&'static str, String)> {
+        self.envs_with(|name| std::env::var(name).ok())
+    }
+    /// Same as `envs`, taking the compilers not given from `env` before the defaults
+    fn envs_with(&self, env: impl Fn(&str) -> Option<String>) -> Vec<(&'static str, String)> {
+        let compiler = |compiler: &Option<String>, name: &str, default: &str| match compiler {
+            _ if self.ccache => {
+                let compiler = compiler.clone().or_else(|| env(name));
+                Some(format!("ccache {}", compiler.as_deref().unwrap_or(default)))
+            }
+            compiler => compiler.clone(),
+        };
+        [
+            ("CC", compiler(&self.cc, "CC", "cc")),
+            ("CXX", compiler(&self.cxx, "CXX", "c++")),
+            ("CFLAGS", self.cflags.clone()),
+            ("LDFLAGS", self.ldflags.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Can't parse install manifest {filepath}: {source}")]
+    FailedParse {
+        filepath: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("Can't write install manifest {filepath}: {source}")]
+    FailedWrite {
+        filepath: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Manifest {
+    pub fn new(version: Version) -> Self {
+        Self {
+            version,
+            variants: Vec::new(),
+            configure_opts: Vec::new(),
+            source_url: None,
+            verification: None,
+            label: None,
+            git: None,
+            built_at: None,
+            toolchain: Toolchain::default(),
+            compiler: None,
+            build_seconds: None,
+            phpup_version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+        }
+    }
+    /// The labeled or versioned install
+    pub fn local(&self) -> Local {
+        match &self.label {
+            Some(label) => Local::Labeled(label.clone()),
+            None => Local::Installed(self.version),
+        }
+    }
+    /// Name of the install directory
+    pub fn install_name(&self) -> String {
+        self.local().to_string()
+    }
+    pub fn filepath(install_dir: impl AsRef<Path>) -> PathBuf {
+        install_dir.as_ref().join(MANIFEST_FILE_NAME)
+    }
+    /// Returns `None` if the version was installed without a manifest
+    pub fn read(install_dir: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        let filepath = Self::filepath(install_dir);
+        if !filepath.is_file() {
+            return Ok(None);
+        }
+        let file = fs::File::open(&filepath)?;
+        serde_json::from_reader(file)
+            .map(Some)
+            .map_err(|source| Error::FailedParse { filepath, source })
+    }
+    pub fn write(&self, install_dir: impl AsRef<Path>) -> Result<(), Error> {
+        let filepath = Self::filepath(install_dir);
+        fs::File::create(&filepath)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writer.flush()
+            })
+            .map_err(|source| Error::FailedWrite { filepath, source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read() {
+        let install_dir = tempfile::tempdir().unwrap();
+        assert!(Manifest::read(&install_dir).unwrap().is_none());
+
+        let mut manifest = Manifest::new("8.2.10".parse().unwrap());
+        manifest.variants = vec!["+intl".parse().unwrap(), "-cgi".parse().unwrap()];
+        manifest.configure_opts = vec!["--enable-intl".to_owned(), "--disable-cgi".to_owned()];
+        manifest.verification = Some(Verification {
+            checksum_policy: ChecksumPolicy::Sha256Only,
+            checksum: Some(Hash::SHA256("0123abcd".to_owned())),
+            user_supplied_checksum: true,
+            signer: None,
+        });
+        manifest.label = Some("8.2-dev".to_owned());
+        manifest.git = Some(GitSource {
+            repository: "https://github.com/php/php-src.git".to_owned(),
+            reference: "PHP-8.2".to_owned(),
+            commit: "1a2b3c4d5e6f".to_owned(),
+        });
+        manifest.built_at = Some(Utc::now());
+        manifest.toolchain = Toolchain {
+            profile: Some("clang".to_owned()),
+            cc: Some("clang".to_owned()),
+            cflags: Some("-O2 -g".to_owned()),
+            ccache: true,
+            ..Default::default()
+        };
+        manifest.compiler = Some("cc (Debian 12.2.0-14) 12.2.0".to_owned());
+        manifest.build_seconds = Some(754);
+        manifest.write(&install_dir).unwrap();
+
+        let json = fs::read_to_string(Manifest::filepath(&install_dir)).unwrap();
+        assert!(json.contains(r#""version": "8.2.10""#));
+        assert!(json.contains(r#""+intl""#));
+        assert!(json.contains(r#""checksum_policy": "sha256-only""#));
+
+        let read = Manifest::read(&install_dir).unwrap().unwrap();
+        assert_eq!(read.version, manifest.version);
+        assert_eq!(read.variants, manifest.variants);
+        assert_eq!(read.configure_opts, manifest.configure_opts);
+        assert_eq!(read.verification, manifest.verification);
+        assert_eq!(read.install_name(), "8.2-dev");
+        assert_eq!(read.git, manifest.git);
+        assert_eq!(read.built_at, manifest.built_at);
+        assert_eq!(read.toolchain, manifest.toolchain);
+        assert_eq!(
+            read.toolchain.envs_with(|_| None),
+            vec![
+                ("CC", "ccache clang".to_owned()),
+                ("CXX", "ccache c++".to_owned()),
+                ("CFLAGS", "-O2 -g".to_owned())
+            ]
+        );
+        assert_eq!(read.compiler, manifest.compiler);
+        assert_eq!(read.build_seconds, Some(754));
+        assert_eq!(
+            read.phpup_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+
+        // written by an older PHP-UP
+        fs::write(
+            Manifest::filepath(&install_dir),
+            r#"{"version": "8.1.12", "variants": [], "configure_opts": []}"#,
+        )
+        .unwrap();
+        let read = Manifest::read(&install_dir).unwrap().unwrap();
+        assert_eq!(read.phpup_version, None);
+        assert_eq!(read.source_url, None);
+        assert_eq!(read.build_seconds, None);
+        assert!(read.toolchain.is_empty());
+
+        assert!(matches!(
+            manifest.write(install_dir.path().join("uninstalled")),
+            Err(Error::FailedWrite { .. })
+        ));
+    }
+
+    #[test]
+    fn ccache_keeps_compilers_from_env() {
+        let toolchain = Toolchain {
+            cxx: Some("clang++".to_owned()),
+            ccache: true,
+            ..Default::default()
+        };
+        let env = |name: &str| match name {
+            "CC" => Some("gcc-12".to_owned()),
+            "CXX" => Some("g++-12".to_owned()),
+            _ => None,
+        };
+        assert_eq!(
+            toolchain.envs_with(env),
+            vec![
+                ("CC", "ccache gcc-12".to_owned()),
+                ("CXX", "ccache clang++".to_owned())
+            ]
+        );
+
+        // left to the environment without ccache
+        let toolchain = Toolchain {
+            ccache: false,
+            ..toolchain
+        };
+        assert_eq!(
+            toolchain.envs_with(env),
+            vec![("CXX", "clang++".to_owned())]
+        );
+    }
+}
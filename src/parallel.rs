@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Mutex;
+use std::thread;
+
+/// Runs `f` for each item on at most `max_workers` threads,
+/// and hands the results to `consume` in the order of `items` as soon as each one is ready.
+/// Once `consume` fails, items not yet started are skipped.
+pub fn for_each_ordered<T, R, E>(
+    items: Vec<T>,
+    max_workers: usize,
+    f: impl Fn(T) -> R + Sync,
+    mut consume: impl FnMut(R) -> Result<(), E>,
+) -> Result<(), E>
+where
+    T: Send,
+    R: Send,
+{
+    let (senders, receivers): (Vec<_>, Vec<_>) = items.iter().map(|_| channel()).unzip();
+    let queue = Mutex::new(items.into_iter().zip(senders).collect::<VecDeque<_>>());
+    let cancelled = AtomicBool::new(false);
+    let workers = max_workers.clamp(1, receivers.len().max(1));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while !cancelled.load(Ordering::SeqCst) {
+                    let Some((item, sender)) = queue.lock().unwrap().pop_front() else {
+                        break;
+                    };
+                    // the receiver is gone only if consuming has failed
+                    sender.send(f(item)).ok();
+                }
+            });
+        }
+        for receiver in receivers {
+            let result = receiver.recv().expect("A worker panicked");
+            if let Err(e) = consume(result) {
+                cancelled.store(true, Ordering::SeqCst);
+                return Err(e);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Maps `items` by `f` on at most `max_workers` threads, keeping the order
+pub fn map<T, R>(items: Vec<T>, max_workers: usize, f: impl Fn(T) -> R + Sync) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+{
+    let mut results = Vec::with_capacity(items.len());
+    for_each_ordered(items, max_workers, f, |result| {
+        results.push(result);
+        Ok::<_, ()>(())
+    })
+    .ok();
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    #[test]
+    fn keep_order_with_bounded_workers() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let results = map((0..8).collect(), 3, |i: u64| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            // later items finish first
+            thread::sleep(Duration::from_millis(40 - i * 5));
+            running.fetch_sub(1, Ordering::SeqCst);
+            i * 10
+        });
+        assert_eq!(results, (0..8).map(|i| i * 10).collect::<Vec<_>>());
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn stop_after_failure() {
+        let started = AtomicUsize::new(0);
+        let mut consumed = Vec::new();
+        let result = for_each_ordered(
+            (0..20).collect(),
+            1,
+            |i: usize| {
+                started.fetch_add(1, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(10));
+                i
+            },
+            |i| {
+                consumed.push(i);
+                if i == 2 {
+                    Err(i)
+                } else {
+                    Ok(())
+                }
+            },
+        );
+        assert_eq!(result, Err(2));
+        assert_eq!(consumed, vec![0, 1, 2]);
+        assert!(started.load(Ordering::SeqCst) < 20);
+    }
+}
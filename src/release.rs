@@ -1,68 +1,132 @@
-use crate::curl;
+use crate::archive;
+use crate::http;
 use crate::version::Version;
-use chrono::{Datelike, NaiveDate, Utc};
+use chrono::NaiveDate;
 use derive_more::Display;
 use serde::{de, Deserialize, Serialize};
-use serde_with::serde_as;
+use serde_with::{serde_as, DeserializeFromStr, SerializeDisplay};
 use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use strum::EnumVariantNames;
 use thiserror::Error;
 
+pub mod cache;
+pub mod directory;
+pub mod index;
+pub mod php_net;
+pub mod qa;
+pub mod schedule;
+
+pub use cache::ReleaseCache;
+pub use directory::Directory;
+pub use index::JsonIndex;
+pub use php_net::PhpNet;
+pub use schedule::Schedule;
+
+pub const DEFAULT_RELEASES_URL: &str = "https://www.php.net/releases/index.php";
+pub const DEFAULT_MIRROR: &str = "https://www.php.net/distributions";
+pub const DEFAULT_MUSEUM_MIRROR: &str = "https://museum.php.net";
+pub const DEFAULT_QA_MIRROR: &str = "https://downloads.php.net";
+
 #[derive(Error, Debug)]
 pub enum FetchError {
     #[error("Can't find releases that matches {0}")]
     NotFoundRelease(Version),
 
+    #[error("Can't find pre-release {0}; qa.php.net lists only the latest pre-release of each branch in QA")]
+    NotFoundPreRelease(Version),
+
     #[error(transparent)]
-    CurlError(#[from] curl::Error),
+    HttpError(#[from] http::Error),
+
+    #[error("Can't read releases from {path}: {source}")]
+    FailedRead {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Can't parse releases from {url}: {source}\n  the response starts with: {snippet}")]
+    MalformedPayload {
+        url: String,
+        snippet: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("A release from {url} doesn't have its version")]
+    MissingVersion { url: String },
+
+    #[error("Can't locate {filename} in the museum; its release doesn't have the version")]
+    NoMuseumPath { filename: String },
+
+    #[error("PHP {version} doesn't have a source archive in any of {formats}")]
+    NoSourceArchive { version: String, formats: String },
+
+    #[error("No mirror is specified to download {filename}")]
+    NoMirror { filename: String },
 
     #[error("Receive error message from release site: {0}")]
     Other(String),
 }
 
-fn fetch_and_parse(
-    version: Option<Version>,
-    max: Option<usize>,
-) -> Result<BTreeMap<Version, Release>, FetchError> {
-    let base_url = "https://www.php.net/releases/index.php";
-    let query = format!(
-        "?json=1{}{}",
-        version
-            .map(|version| format!("&version={}", version))
-            .unwrap_or_default(),
-        max.map(|max| format!("&max={}", max)).unwrap_or_default(),
-    );
-    let url = &format!("{}{}", base_url, query);
-    let json = curl::get_as_slice(url)?;
-
-    let resp: Response =
-        serde_json::from_slice(&json).unwrap_or_else(|_| panic!("Can't parse json from {}", url));
-    match resp {
-        Response::Map(releases) => Ok(releases),
-        Response::One(release) => Ok([(release.version.unwrap(), release)].into_iter().collect()),
-        Response::Error { msg } => {
-            if msg.starts_with("Unknown version") {
-                Err(FetchError::NotFoundRelease(version.unwrap()))
-            } else {
-                Err(FetchError::Other(msg.to_owned()))
-            }
-        }
+/// Where to look up PHP releases and their source archives
+pub trait ReleaseSource: Sync {
+    /// Returns all releases included by `version`
+    fn fetch_all(&self, version: Version) -> Result<BTreeMap<Version, Release>, FetchError>;
+
+    /// Returns the latest release included by `version`.
+    /// Pre-releases are candidates only if `version` is a pre-release.
+    fn fetch_latest(&self, version: Version) -> Result<Release, FetchError> {
+        self.fetch_all(version)?
+            .into_iter()
+            .rev()
+            .find(|(release_version, _)| {
+                version.is_pre_release() || !release_version.is_pre_release()
+            })
+            .map(|(_, release)| release)
+            .ok_or(FetchError::NotFoundRelease(version))
+    }
+
+    fn fetch_oldest_patch(&self, version: Version) -> Result<Release, FetchError> {
+        let oldest_minor_version =
+            Version::from_numbers(version.major_version(), version.minor_version(), Some(0));
+        self.fetch_latest(oldest_minor_version)
     }
 }
 
-pub fn fetch_all(version: Version) -> Result<BTreeMap<Version, Release>, FetchError> {
-    fetch_and_parse(Some(version), Some(1000))
+impl FetchError {
+    fn malformed(url: &str, body: &[u8], source: serde_json::Error) -> Self {
+        Self::MalformedPayload {
+            url: url.to_owned(),
+            snippet: snippet(body),
+            source,
+        }
+    }
 }
 
-pub fn fetch_latest(version: Version) -> Result<Release, FetchError> {
-    let mut latest = fetch_and_parse(Some(version), None)?;
-    let version = *latest.keys().next().unwrap();
-    Ok(latest.remove(&version).unwrap())
+const SNIPPET_LEN: usize = 100;
+
+/// The head of `body` on one line, to tell what was returned instead of releases
+fn snippet(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    let mut words = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if words.chars().count() > SNIPPET_LEN {
+        words = words.chars().take(SNIPPET_LEN).collect::<String>() + "...";
+    }
+    if words.is_empty() {
+        "(empty)".to_owned()
+    } else {
+        words
+    }
 }
 
-pub fn fetch_oldest_patch(version: Version) -> Result<Release, FetchError> {
-    let oldest_minor_version =
-        Version::from_numbers(version.major_version(), version.minor_version(), Some(0));
-    fetch_latest(oldest_minor_version)
+fn filter_by(releases: BTreeMap<Version, Release>, version: Version) -> BTreeMap<Version, Release> {
+    releases
+        .into_iter()
+        .filter(|(release_version, _)| version.includes(release_version))
+        .collect()
 }
 
 #[derive(Deserialize, Debug)]
@@ -84,7 +148,10 @@ pub struct Release {
     source: Vec<Source>,
     #[serde(rename = "windows")]
     windows_binary: Option<Vec<Source>>,
-    #[serde(deserialize_with = "date_deserializer")]
+    #[serde(
+        deserialize_with = "date_deserializer",
+        serialize_with = "date_serializer"
+    )]
     pub date: NaiveDate,
     museum: Option<bool>,
     pub version: Option<Version>,
@@ -130,11 +197,16 @@ pub struct File {
     checksum: Option<Hash>,
     // TODO: Option<NaiveTime>
     date: Option<String>,
+    // absolute download url; php.net releases are resolved from `filename` instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
 }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Display)]
 #[serde(rename_all(deserialize = "lowercase", serialize = "lowercase"))]
 pub enum Hash {
+    #[display(fmt = "sha256:{}", _0)]
     SHA256(String),
+    #[display(fmt = "md5:{}", _0)]
     MD5(String),
 }
 
@@ -143,10 +215,107 @@ pub enum ChecksumError {
     #[error("Invalid checksum\nexptected: {expected}\ngot: {got}")]
     InvalidChecksum { expected: String, got: String },
 
+    #[error("{filename} has no checksum, which the checksum policy `{policy}` refuses; Pass one by `--checksum sha256:<hex>`")]
+    NoChecksum {
+        filename: String,
+        policy: ChecksumPolicy,
+    },
+
+    #[error("{filename} has only an MD5 checksum, which the checksum policy `{policy}` refuses; Pass one by `--checksum sha256:<hex>`")]
+    WeakChecksum {
+        filename: String,
+        policy: ChecksumPolicy,
+    },
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
 
+/// What source archives need to have to be installed
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Display,
+    EnumVariantNames,
+    SerializeDisplay,
+    DeserializeFromStr,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ChecksumPolicy {
+    /// Refuse archives without a checksum
+    #[display(fmt = "strict")]
+    Strict,
+    /// Refuse archives without a SHA-256 checksum
+    #[display(fmt = "sha256-only")]
+    Sha256Only,
+    /// Only warn about archives without a checksum
+    #[default]
+    #[display(fmt = "warn")]
+    Warn,
+}
+
+#[derive(Error, Debug)]
+pub enum ParseChecksumPolicyError {
+    #[error("Unknown checksum policy: {0}")]
+    UnknownPolicy(String),
+}
+
+impl FromStr for ChecksumPolicy {
+    type Err = ParseChecksumPolicyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(ChecksumPolicy::Strict),
+            "sha256-only" => Ok(ChecksumPolicy::Sha256Only),
+            "warn" => Ok(ChecksumPolicy::Warn),
+            _ => Err(ParseChecksumPolicyError::UnknownPolicy(s.to_owned())),
+        }
+    }
+}
+
+impl ChecksumPolicy {
+    /// Checks if `checksum` of `filename` is acceptable before downloading it
+    pub fn check(&self, filename: &str, checksum: Option<&Hash>) -> Result<(), ChecksumError> {
+        match (self, checksum) {
+            (ChecksumPolicy::Strict | ChecksumPolicy::Sha256Only, None) => {
+                Err(ChecksumError::NoChecksum {
+                    filename: filename.to_owned(),
+                    policy: *self,
+                })
+            }
+            (ChecksumPolicy::Sha256Only, Some(Hash::MD5(_))) => Err(ChecksumError::WeakChecksum {
+                filename: filename.to_owned(),
+                policy: *self,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ParseHashError {
+    #[error("Invalid checksum: \"{0}\"; Use `sha256:<hex>`")]
+    InvalidFormat(String),
+}
+
+/// `sha256:<hex>` given by the user
+impl FromStr for Hash {
+    type Err = ParseHashError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("sha256", hex))
+                if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Ok(Hash::SHA256(hex.to_ascii_lowercase()))
+            }
+            _ => Err(ParseHashError::InvalidFormat(s.to_owned())),
+        }
+    }
+}
+
 use sha2::Digest;
 impl Hash {
     pub fn hash_type(&self) -> &'static str {
@@ -155,6 +324,11 @@ impl Hash {
             Hash::MD5(_) => "MD5",
         }
     }
+    pub fn value(&self) -> &str {
+        match self {
+            Hash::SHA256(checksum) | Hash::MD5(checksum) => checksum,
+        }
+    }
     pub fn verify(&self, mut data: impl std::io::Read) -> Result<(), ChecksumError> {
         let (checksum, hash) = match self {
             Hash::SHA256(checksum) => {
@@ -191,49 +365,132 @@ pub enum Support {
     EndOfLife,
 }
 
+impl Support {
+    pub fn color(&self) -> colored::Color {
+        match self {
+            Support::ActiveSupport => colored::Color::Green,
+            Support::SecurityFixesOnly => colored::Color::Yellow,
+            Support::EndOfLife => colored::Color::Red,
+        }
+    }
+    /// Column of `list` and `list-remote` padded to the longest status, blank if unknown
+    pub fn column(support: Option<Self>) -> colored::ColoredString {
+        use colored::Colorize;
+        let column = format!(
+            "{:<19}",
+            support
+                .map(|support| support.to_string())
+                .unwrap_or_default()
+        );
+        match support {
+            Some(support) => column.color(support.color()),
+            None => column.normal(),
+        }
+    }
+}
+
+/// Source archive of a release with the URLs to download it from
+#[derive(Debug)]
+pub struct SourceUrls<'a> {
+    pub filename: &'a str,
+    /// In order of the mirrors, at least one
+    pub urls: Vec<String>,
+    pub checksum: Option<&'a Hash>,
+}
+
 impl Release {
-    fn source_file(&self, extention: &str) -> Option<&File> {
+    fn source_file(&self, format: archive::Format) -> Option<&File> {
         self.source.iter().find_map(|source| match source {
-            Source::File(file) if file.filename.ends_with(extention) => Some(file),
+            Source::File(file) if file.filename.ends_with(format.extension()) => Some(file),
             _ => None,
         })
     }
-    pub fn source_url(&self) -> (String, Option<&Hash>) {
-        let source_file = self.source_file(".tar.gz").unwrap();
-        let url = if self.museum == Some(true) {
-            let major_version = self.version.unwrap().major_version();
-            format!(
-                "https://museum.php.net/php{}/{}",
-                major_version, source_file.filename
-            )
+    /// Returns the source archive in the first available format of `formats`
+    /// with its download URLs in order of `mirrors`, `museum_mirrors` or `qa_mirrors`
+    pub fn source_urls(
+        &self,
+        formats: &[archive::Format],
+        mirrors: &[&str],
+        museum_mirrors: &[&str],
+        qa_mirrors: &[&str],
+    ) -> Result<SourceUrls<'_>, FetchError> {
+        let source_file = formats
+            .iter()
+            .find_map(|format| self.source_file(*format))
+            .ok_or_else(|| FetchError::NoSourceArchive {
+                version: self
+                    .version
+                    .map_or_else(|| self.date.to_string(), |version| version.to_string()),
+                formats: formats
+                    .iter()
+                    .map(|format| format.extension())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })?;
+        let urls = if let Some(url) = &source_file.url {
+            // pre-releases are in the directory of each release manager, e.g. `/~saki/`
+            match url
+                .strip_prefix(DEFAULT_QA_MIRROR)
+                .filter(|path| path.starts_with("/~"))
+            {
+                Some(path) => qa_mirrors
+                    .iter()
+                    .map(|mirror| format!("{}{}", mirror.trim_end_matches('/'), path))
+                    .collect(),
+                None => vec![url.clone()],
+            }
+        } else if self.museum == Some(true) {
+            let major_version = self
+                .version
+                .ok_or_else(|| FetchError::NoMuseumPath {
+                    filename: source_file.filename.clone(),
+                })?
+                .major_version();
+            museum_mirrors
+                .iter()
+                .map(|mirror| {
+                    format!(
+                        "{}/php{}/{}",
+                        mirror.trim_end_matches('/'),
+                        major_version,
+                        source_file.filename
+                    )
+                })
+                .collect()
         } else {
-            format!("https://www.php.net/distributions/{}", source_file.filename)
-            // format!("http://jp1.php.net/get/{}/from/this/mirror/", filename)
+            mirrors
+                .iter()
+                .map(|mirror| format!("{}/{}", mirror.trim_end_matches('/'), source_file.filename))
+                .collect()
         };
-        (url, source_file.checksum.as_ref())
-    }
-    pub fn calculate_support(&self) -> Support {
-        let release_date = self.date;
-        let release_year = release_date.year();
-        let active_support_deadline = release_date
-            .with_year(release_year + 2)
-            .unwrap_or_else(|| NaiveDate::from_yo(release_year + 1, 1).succ());
-        let security_support_deadline = release_date
-            .with_year(release_year + 3)
-            .unwrap_or_else(|| NaiveDate::from_yo(release_year + 2, 1).succ());
-        let today = Utc::now().naive_local().date();
-
-        if today < active_support_deadline {
-            Support::ActiveSupport
-        } else if today < security_support_deadline {
-            Support::SecurityFixesOnly
-        } else {
-            Support::EndOfLife
+        if urls.is_empty() {
+            return Err(FetchError::NoMirror {
+                filename: source_file.filename.clone(),
+            });
         }
+        Ok(SourceUrls {
+            filename: &source_file.filename,
+            urls,
+            checksum: source_file.checksum.as_ref(),
+        })
+    }
+    /// Whether the release is tagged as fixing security issues
+    pub fn is_security(&self) -> bool {
+        self.tags
+            .iter()
+            .flatten()
+            .any(|tag| matches!(tag, Tag::Security))
     }
 }
 
-fn date_deserializer<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+fn date_serializer<S>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_str(&date.format("%d %b %Y"))
+}
+
+pub(crate) fn date_deserializer<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
 where
     D: de::Deserializer<'de>,
 {
@@ -321,24 +578,141 @@ mod tests {
         });
     }
     #[test]
-    fn fetch_all_major_test() {
-        let releases = fetch_all("3".parse().unwrap());
-        assert!(releases.is_ok());
-        assert!(!releases.unwrap().is_empty());
-        let releases = fetch_all("5".parse().unwrap());
-        assert!(releases.is_ok());
-        assert!(!releases.unwrap().is_empty());
-        let releases = fetch_all("8".parse().unwrap());
-        assert!(releases.is_ok());
-        assert!(!releases.unwrap().is_empty());
+    fn source_urls_with_mirrors() {
+        let json = r#"
+            {
+                "date": "10 Jan 2019",
+                "source": [
+                    {
+                        "filename": "php-7.0.33.tar.gz",
+                        "name": "PHP 7.0.33 (tar.gz)",
+                        "sha256": "d71a6ecb6b13dc53fed7532a7f8f949c4044806f067502f8fb6f9facbb40452a"
+                    }
+                ],
+                "museum": true,
+                "version": "7.0.33"
+            }
+        "#;
+        let mut release: Release = serde_json::from_str(json).unwrap();
+        let mirrors = ["https://proxy.example.com/dist/", DEFAULT_MIRROR];
+        let museum_mirrors = ["https://proxy.example.com/museum", DEFAULT_MUSEUM_MIRROR];
+
+        let SourceUrls { urls, .. } = release
+            .source_urls(&archive::DEFAULT_PREFERENCE, &mirrors, &museum_mirrors, &[])
+            .unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                "https://proxy.example.com/museum/php7/php-7.0.33.tar.gz",
+                "https://museum.php.net/php7/php-7.0.33.tar.gz"
+            ]
+        );
+        let version = release.version.take();
+        assert!(matches!(
+            release.source_urls(&archive::DEFAULT_PREFERENCE, &mirrors, &museum_mirrors, &[]),
+            Err(FetchError::NoMuseumPath { filename }) if filename == "php-7.0.33.tar.gz"
+        ));
+        release.version = version;
+
+        release.museum = None;
+        let SourceUrls { urls, .. } = release
+            .source_urls(&archive::DEFAULT_PREFERENCE, &mirrors, &museum_mirrors, &[])
+            .unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                "https://proxy.example.com/dist/php-7.0.33.tar.gz",
+                "https://www.php.net/distributions/php-7.0.33.tar.gz"
+            ]
+        );
+
+        assert!(matches!(
+            release.source_urls(&archive::DEFAULT_PREFERENCE, &[], &museum_mirrors, &[]),
+            Err(FetchError::NoMirror { filename }) if filename == "php-7.0.33.tar.gz"
+        ));
+
+        release.source.clear();
+        assert!(matches!(
+            release.source_urls(&archive::DEFAULT_PREFERENCE, &mirrors, &museum_mirrors, &[]),
+            Err(FetchError::NoSourceArchive { version, .. }) if version == "7.0.33"
+        ));
+    }
+    #[test]
+    fn source_urls_in_preferred_format() {
+        let json = r#"
+            {
+                "date": "16 Dec 2021",
+                "source": [
+                    { "filename": "php-8.1.1.tar.gz", "name": "PHP 8.1.1 (tar.gz)", "sha256": "gz" },
+                    { "filename": "php-8.1.1.tar.bz2", "name": "PHP 8.1.1 (tar.bz2)", "sha256": "bz2" },
+                    { "filename": "php-8.1.1.tar.xz", "name": "PHP 8.1.1 (tar.xz)", "sha256": "xz" }
+                ],
+                "version": "8.1.1"
+            }
+        "#;
+        let release: Release = serde_json::from_str(json).unwrap();
+
+        let SourceUrls { urls, checksum, .. } = release
+            .source_urls(&archive::DEFAULT_PREFERENCE, &[DEFAULT_MIRROR], &[], &[])
+            .unwrap();
+        assert_eq!(urls, vec![format!("{}/php-8.1.1.tar.xz", DEFAULT_MIRROR)]);
+        assert_eq!(checksum.map(Hash::value), Some("xz"));
+
+        let SourceUrls { urls, .. } = release
+            .source_urls(
+                &[archive::Format::Bz2, archive::Format::Gz],
+                &[DEFAULT_MIRROR],
+                &[],
+                &[],
+            )
+            .unwrap();
+        assert_eq!(urls, vec![format!("{}/php-8.1.1.tar.bz2", DEFAULT_MIRROR)]);
+    }
+    #[test]
+    fn checksum_policy() {
+        let sha256 = "sha256:561DC4ACD5386E47F25BE76F2C8DF6AE854756469159248313BCF276E282FBB3"
+            .parse::<Hash>()
+            .unwrap();
+        assert_eq!(
+            sha256.value(),
+            "561dc4acd5386e47f25be76f2c8df6ae854756469159248313bcf276e282fbb3"
+        );
+        assert!("md5:0123".parse::<Hash>().is_err());
+        assert!("sha256:0123".parse::<Hash>().is_err());
+
+        let md5 = Hash::MD5("0123".to_owned());
+        let filename = "php-5.6.40.tar.gz";
+        for policy in [
+            ChecksumPolicy::Strict,
+            ChecksumPolicy::Sha256Only,
+            ChecksumPolicy::Warn,
+        ] {
+            assert!(policy.check(filename, Some(&sha256)).is_ok());
+        }
+        assert!(ChecksumPolicy::Strict.check(filename, Some(&md5)).is_ok());
+        assert!(matches!(
+            ChecksumPolicy::Sha256Only.check(filename, Some(&md5)),
+            Err(ChecksumError::WeakChecksum { .. })
+        ));
+        assert!(matches!(
+            ChecksumPolicy::Strict.check(filename, None),
+            Err(ChecksumError::NoChecksum { .. })
+        ));
+        assert!(ChecksumPolicy::Warn.check(filename, None).is_ok());
+        assert_eq!(
+            "sha256-only".parse::<ChecksumPolicy>().unwrap().to_string(),
+            "sha256-only"
+        );
     }
     #[test]
-    fn fetch_latest_test() {
-        let latest_release = fetch_latest("7.0".parse().unwrap());
-        assert!(latest_release.is_ok());
+    fn snippet_of_body() {
         assert_eq!(
-            latest_release.unwrap().version.unwrap(),
-            "7.0.33".parse().unwrap()
+            snippet(b"<html>\n  <body>Not Found</body>\n"),
+            "<html> <body>Not Found</body>"
         );
+        assert_eq!(snippet(b"  \n"), "(empty)");
+        let long = snippet("x".repeat(500).as_bytes());
+        assert_eq!(long.len(), SNIPPET_LEN + 3);
+        assert!(long.ends_with("..."));
     }
 }
@@ -0,0 +1,105 @@
+use super::{FetchError, Release};
+use crate::http::{Conditional, Transport, Validators};
+use crate::version::Version;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_TTL_HOURS: i64 = 24;
+
+/// Parsed releases of each release API URL: `<dir>/<sha256 of url>.json`.
+/// A stale entry is revalidated by `If-None-Match` / `If-Modified-Since`.
+pub struct ReleaseCache {
+    dir: PathBuf,
+    /// `None` never expires entries, which is used in offline mode
+    ttl: Option<Duration>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    fetched_at: DateTime<Utc>,
+    #[serde(flatten)]
+    validators: Validators,
+    releases: BTreeMap<Version, Release>,
+}
+
+impl ReleaseCache {
+    pub fn new(dir: impl AsRef<Path>, ttl: Option<Duration>) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+            ttl,
+        }
+    }
+    fn filepath(&self, url: &str) -> PathBuf {
+        let key = sha2::Sha256::digest(url.as_bytes());
+        self.dir.join(format!("{:x}.json", key))
+    }
+    fn read(&self, url: &str) -> Option<Entry> {
+        let json = fs::read(self.filepath(url)).ok()?;
+        // a broken entry is just fetched again
+        serde_json::from_slice::<Entry>(&json)
+            .ok()
+            .filter(|entry| entry.url == url)
+    }
+    fn write(&self, entry: &Entry) {
+        // failing to cache must not fail the command
+        let write = || -> Result<(), Box<dyn std::error::Error>> {
+            fs::create_dir_all(&self.dir)?;
+            let file = tempfile::NamedTempFile::new_in(&self.dir)?;
+            serde_json::to_writer(&file, entry)?;
+            file.persist(self.filepath(&entry.url))?;
+            Ok(())
+        };
+        write().ok();
+    }
+    fn is_fresh(&self, entry: &Entry) -> bool {
+        self.ttl
+            .is_none_or(|ttl| Utc::now() - entry.fetched_at < ttl)
+    }
+    /// Returns the releases from the cache while it's fresh, otherwise fetches `url` and parses it by `parse`
+    pub fn fetch(
+        &self,
+        http: &dyn Transport,
+        url: &str,
+        parse: impl FnOnce(&[u8]) -> Result<BTreeMap<Version, Release>, FetchError>,
+    ) -> Result<BTreeMap<Version, Release>, FetchError> {
+        let cached = match self.read(url) {
+            Some(entry) if self.is_fresh(&entry) => return Ok(entry.releases),
+            cached => cached,
+        };
+        let validators = cached
+            .as_ref()
+            .map(|entry| entry.validators.clone())
+            .unwrap_or_default();
+        let entry = match (http.get_if_modified(url, &validators)?, cached) {
+            (Conditional::NotModified, Some(entry)) => Entry {
+                fetched_at: Utc::now(),
+                ..entry
+            },
+            (Conditional::NotModified, None) => {
+                return Err(FetchError::Other(format!(
+                    "{} responded with 304 Not Modified to an unconditional request",
+                    url
+                )))
+            }
+            (Conditional::Modified { body, validators }, _) => Entry {
+                url: url.to_owned(),
+                fetched_at: Utc::now(),
+                validators,
+                releases: parse(&body)?,
+            },
+        };
+        self.write(&entry);
+        Ok(entry.releases)
+    }
+    pub fn clear(&self) -> std::io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
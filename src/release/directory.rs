@@ -0,0 +1,143 @@
+use super::{filter_by, FetchError, File, Hash, Release, ReleaseSource, Source};
+use crate::archive;
+use crate::version::Version;
+use chrono::{DateTime, Local, NaiveDate};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Local directory of source archives named `php-<version>.tar.{xz,gz,bz2}`.
+/// A checksum is read from `<archive>.sha256` (the output of `sha256sum`) if it exists.
+pub struct Directory {
+    dir: PathBuf,
+}
+
+impl Directory {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+    fn source_file(&self, path: &Path) -> Option<(Version, File)> {
+        let filename = path.file_name()?.to_str()?;
+        let format = archive::Format::from_filename(filename)?;
+        let version = filename
+            .strip_prefix("php-")?
+            .strip_suffix(format.extension())?
+            .parse::<Version>()
+            .ok()?;
+        let mut checksum_path = path.as_os_str().to_owned();
+        checksum_path.push(".sha256");
+        let checksum = fs::read_to_string(checksum_path)
+            .ok()
+            .and_then(|sums| sums.split_whitespace().next().map(str::to_owned))
+            .map(Hash::SHA256);
+        let file = File {
+            filename: filename.to_owned(),
+            name: format!("PHP {} ({})", version, &format.extension()[1..]),
+            checksum,
+            date: None,
+            url: Some(format!("file://{}", path.display())),
+        };
+        Some((version, file))
+    }
+}
+
+fn modified_date(path: &Path) -> NaiveDate {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| DateTime::<Local>::from(modified).naive_local().date())
+        .unwrap_or_else(|_| Local::now().naive_local().date())
+}
+
+impl ReleaseSource for Directory {
+    fn fetch_all(&self, version: Version) -> Result<BTreeMap<Version, Release>, FetchError> {
+        let dir = self
+            .dir
+            .canonicalize()
+            .map_err(|source| FetchError::FailedRead {
+                path: self.dir.clone(),
+                source,
+            })?;
+        let entries = fs::read_dir(&dir).map_err(|source| FetchError::FailedRead {
+            path: dir.clone(),
+            source,
+        })?;
+        let mut releases = BTreeMap::<Version, Release>::new();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let Some((release_version, file)) = self.source_file(&path) else {
+                continue;
+            };
+            // archives of the same version in several formats make up one release
+            releases
+                .entry(release_version)
+                .or_insert_with(|| Release {
+                    announcement: None,
+                    tags: None,
+                    source: Vec::new(),
+                    windows_binary: None,
+                    date: modified_date(&path),
+                    museum: None,
+                    version: Some(release_version),
+                })
+                .source
+                .push(Source::File(file));
+        }
+        Ok(filter_by(releases, version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{Format, DEFAULT_PREFERENCE};
+    use crate::release::SourceUrls;
+
+    #[test]
+    fn scan_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        for filename in [
+            "php-8.1.23.tar.gz",
+            "php-8.2.9.tar.bz2",
+            "php-8.2.10.tar.gz",
+            "php-8.2.10.tar.xz",
+            "php-8.3.0RC1.tar.gz",
+            "php-8.2.11.zip",
+            "README",
+        ] {
+            fs::write(dir.path().join(filename), b"").unwrap();
+        }
+        fs::write(
+            dir.path().join("php-8.2.10.tar.gz.sha256"),
+            "561dc4acd5386e47f25be76f2c8df6ae854756469159248313bcf276e282fbb3  php-8.2.10.tar.gz\n",
+        )
+        .unwrap();
+        let directory = Directory::new(&dir);
+
+        let releases = directory.fetch_all("8.2".parse().unwrap()).unwrap();
+        assert_eq!(
+            releases.keys().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["8.2.9", "8.2.10"]
+        );
+
+        let latest = directory.fetch_latest("8".parse().unwrap()).unwrap();
+        let dir_path = dir.path().canonicalize().unwrap();
+        let SourceUrls { urls, .. } = latest
+            .source_urls(&DEFAULT_PREFERENCE, &[], &[], &[])
+            .unwrap();
+        let path = dir_path.join("php-8.2.10.tar.xz");
+        assert_eq!(urls, vec![format!("file://{}", path.display())]);
+
+        let SourceUrls { urls, checksum, .. } =
+            latest.source_urls(&[Format::Gz], &[], &[], &[]).unwrap();
+        let path = dir_path.join("php-8.2.10.tar.gz");
+        assert_eq!(urls, vec![format!("file://{}", path.display())]);
+        assert_eq!(
+            checksum.map(Hash::value),
+            Some("561dc4acd5386e47f25be76f2c8df6ae854756469159248313bcf276e282fbb3")
+        );
+
+        let pre_release = directory.fetch_latest("8.3.0RC1".parse().unwrap()).unwrap();
+        assert_eq!(pre_release.version, Some("8.3.0RC1".parse().unwrap()));
+    }
+}
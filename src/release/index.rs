@@ -0,0 +1,163 @@
+//! Static JSON index of releases in the same format as `https://www.php.net/releases/index.php?json=1&max=1000`
+//!
+//! ```json
+//! {
+//!     "8.2.10": {
+//!         "date": "31 Aug 2023",
+//!         "source": [
+//!             { "filename": "php-8.2.10.tar.gz", "name": "PHP 8.2.10 (tar.gz)", "sha256": "..." }
+//!         ]
+//!     }
+//! }
+//! ```
+//!
+//! A source file can have `url`, which is resolved relative to the index.
+//! Otherwise it's downloaded from the mirrors like php.net releases.
+
+use super::{filter_by, FetchError, Release, ReleaseCache, ReleaseSource, Source};
+use crate::http::Transport;
+use crate::version::Version;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub struct JsonIndex<'a> {
+    http: &'a dyn Transport,
+    cache: ReleaseCache,
+    /// URL or file path of the index
+    location: String,
+}
+
+impl<'a> JsonIndex<'a> {
+    pub fn new(http: &'a dyn Transport, cache: ReleaseCache, location: impl Into<String>) -> Self {
+        Self {
+            http,
+            cache,
+            location: location.into(),
+        }
+    }
+    fn is_remote(&self) -> bool {
+        self.location.contains("://")
+    }
+    fn parse(&self, json: &[u8]) -> Result<BTreeMap<Version, Release>, FetchError> {
+        serde_json::from_slice(json)
+            .map_err(|source| FetchError::malformed(&self.location, json, source))
+    }
+    /// Resolves a relative `url` against the directory of the index
+    fn resolve(&self, url: &str) -> String {
+        if url.contains("://") {
+            url.to_owned()
+        } else if self.is_remote() {
+            let base = &self.location[..self.location.rfind('/').unwrap() + 1];
+            format!("{}{}", base, url)
+        } else {
+            let index = Path::new(&self.location);
+            let base = index
+                .canonicalize()
+                .ok()
+                .and_then(|index| index.parent().map(Path::to_owned))
+                .unwrap_or_default();
+            format!("file://{}", base.join(url).display())
+        }
+    }
+}
+
+impl ReleaseSource for JsonIndex<'_> {
+    fn fetch_all(&self, version: Version) -> Result<BTreeMap<Version, Release>, FetchError> {
+        let releases = if self.is_remote() {
+            self.cache
+                .fetch(self.http, &self.location, |json| self.parse(json))?
+        } else {
+            let json = fs::read(&self.location).map_err(|source| FetchError::FailedRead {
+                path: self.location.clone().into(),
+                source,
+            })?;
+            self.parse(&json)?
+        };
+        let releases = releases
+            .into_iter()
+            .map(|(release_version, mut release)| {
+                release.version = Some(release_version);
+                for source in release.source.iter_mut() {
+                    if let Source::File(file) = source {
+                        file.url = file.url.as_deref().map(|url| self.resolve(url));
+                    }
+                }
+                (release_version, release)
+            })
+            .collect();
+        Ok(filter_by(releases, version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::DEFAULT_PREFERENCE;
+    use crate::http::tests::{backends, StandIn};
+    use crate::release::{SourceUrls, DEFAULT_MIRROR};
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/releases.json");
+
+    #[test]
+    fn fetch_from_file() {
+        let http = &*backends()[0];
+        let cache_dir = tempfile::tempdir().unwrap();
+        let index = JsonIndex::new(http, ReleaseCache::new(&cache_dir, None), FIXTURE);
+
+        let releases = index.fetch_all("7".parse().unwrap()).unwrap();
+        assert_eq!(
+            releases.keys().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["7.4.32", "7.4.33"]
+        );
+
+        let latest = index.fetch_latest("8".parse().unwrap()).unwrap();
+        assert_eq!(latest.version, Some("8.2.10".parse().unwrap()));
+        let SourceUrls { urls, checksum, .. } = latest
+            .source_urls(&DEFAULT_PREFERENCE, &[DEFAULT_MIRROR], &[], &[])
+            .unwrap();
+        assert_eq!(urls, vec![format!("{}/php-8.2.10.tar.gz", DEFAULT_MIRROR)]);
+        assert!(checksum.is_some());
+
+        let pre_release = index.fetch_latest("8.3.0RC1".parse().unwrap()).unwrap();
+        let SourceUrls { urls, .. } = pre_release
+            .source_urls(&DEFAULT_PREFERENCE, &[DEFAULT_MIRROR], &[], &[])
+            .unwrap();
+        let fixtures_dir = Path::new(FIXTURE).parent().unwrap();
+        assert_eq!(
+            urls,
+            vec![format!(
+                "file://{}/pre/php-8.3.0RC1.tar.gz",
+                fixtures_dir.display()
+            )]
+        );
+
+        assert!(matches!(
+            index.fetch_latest("5".parse().unwrap()),
+            Err(FetchError::NotFoundRelease(_))
+        ));
+    }
+
+    #[test]
+    fn fetch_from_url() {
+        let server = StandIn::serve(fs::read(FIXTURE).unwrap(), 0);
+        let http = &*backends()[0];
+        let cache_dir = tempfile::tempdir().unwrap();
+        let index = JsonIndex::new(
+            http,
+            ReleaseCache::new(&cache_dir, None),
+            server.url.replace("php-8.2.10.tar.gz", "index.json"),
+        );
+
+        let pre_release = index.fetch_latest("8.3.0RC1".parse().unwrap()).unwrap();
+        let SourceUrls { urls, .. } = pre_release
+            .source_urls(&DEFAULT_PREFERENCE, &[], &[], &[])
+            .unwrap();
+        assert_eq!(
+            urls,
+            vec![server
+                .url
+                .replace("php-8.2.10.tar.gz", "pre/php-8.3.0RC1.tar.gz")]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use super::{filter_by, qa, FetchError, Release, ReleaseCache, ReleaseSource, Response};
+use crate::http::Transport;
+use crate::version::Version;
+use std::collections::BTreeMap;
+
+/// Releases published by the php.net release API.
+/// Pre-releases are looked up on the QA release API.
+pub struct PhpNet<'a> {
+    http: &'a dyn Transport,
+    cache: ReleaseCache,
+    releases_url: &'a str,
+    qa_releases_url: &'a str,
+}
+
+impl<'a> PhpNet<'a> {
+    pub fn new(
+        http: &'a dyn Transport,
+        cache: ReleaseCache,
+        releases_url: &'a str,
+        qa_releases_url: &'a str,
+    ) -> Self {
+        Self {
+            http,
+            cache,
+            releases_url,
+            qa_releases_url,
+        }
+    }
+    /// Fetches all releases of the major version, which are cached and shared by any query under it
+    fn fetch_major(&self, major_version: usize) -> Result<BTreeMap<Version, Release>, FetchError> {
+        let url = format!(
+            "{}?json=1&version={}&max=1000",
+            self.releases_url, major_version
+        );
+        self.cache.fetch(self.http, &url, |json| {
+            let resp: Response = serde_json::from_slice(json)
+                .map_err(|source| FetchError::malformed(&url, json, source))?;
+            match resp {
+                Response::Map(releases) => Ok(releases
+                    .into_iter()
+                    .map(|(version, mut release)| {
+                        release.version = Some(version);
+                        (version, release)
+                    })
+                    .collect()),
+                Response::One(release) => {
+                    let version = release
+                        .version
+                        .ok_or_else(|| FetchError::MissingVersion { url: url.clone() })?;
+                    Ok([(version, release)].into_iter().collect())
+                }
+                Response::Error { msg } => {
+                    if msg.starts_with("Unknown version") {
+                        Err(FetchError::NotFoundRelease(Version::from_major(
+                            major_version,
+                        )))
+                    } else {
+                        Err(FetchError::Other(msg.to_owned()))
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl ReleaseSource for PhpNet<'_> {
+    fn fetch_all(&self, version: Version) -> Result<BTreeMap<Version, Release>, FetchError> {
+        let releases = self.fetch_major(version.major_version())?;
+        Ok(filter_by(releases, version))
+    }
+    fn fetch_latest(&self, version: Version) -> Result<Release, FetchError> {
+        if version.is_pre_release() {
+            return qa::fetch_latest(self.http, &self.cache, version, self.qa_releases_url);
+        }
+        self.fetch_all(version)?
+            .into_iter()
+            .next_back()
+            .map(|(_, release)| release)
+            .ok_or(FetchError::NotFoundRelease(version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::tests::{backends, StandIn};
+    use crate::http::{self, Offline};
+    use chrono::Duration;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn fetch_from_api() {
+        let index = include_bytes!("../../tests/fixtures/releases.json").to_vec();
+        let server = StandIn::serve(index, 0);
+        let cache_dir = tempfile::tempdir().unwrap();
+        let http = &*backends()[0];
+        let php_net = PhpNet::new(
+            http,
+            ReleaseCache::new(&cache_dir, Some(Duration::hours(1))),
+            &server.url,
+            &server.url,
+        );
+
+        let releases = php_net.fetch_all("7".parse().unwrap()).unwrap();
+        assert!(releases.contains_key(&"7.4.33".parse().unwrap()));
+        let latest = php_net.fetch_latest("7.4".parse().unwrap()).unwrap();
+        assert_eq!(latest.version, Some("7.4.33".parse().unwrap()));
+        // the list of 7.x is shared by both
+        assert_eq!(server.requests.load(Ordering::SeqCst), 1);
+
+        // revalidated once it's expired
+        let php_net = PhpNet::new(
+            http,
+            ReleaseCache::new(&cache_dir, Some(Duration::zero())),
+            &server.url,
+            &server.url,
+        );
+        let latest = php_net.fetch_latest("7.4".parse().unwrap()).unwrap();
+        assert_eq!(latest.version, Some("7.4.33".parse().unwrap()));
+        assert_eq!(server.not_modified.load(Ordering::SeqCst), 1);
+
+        // offline: the cache never expires, and anything else is unavailable
+        let php_net = PhpNet::new(
+            &Offline,
+            ReleaseCache::new(&cache_dir, None),
+            &server.url,
+            &server.url,
+        );
+        assert!(php_net.fetch_latest("7.4".parse().unwrap()).is_ok());
+        assert!(matches!(
+            php_net.fetch_all("5".parse().unwrap()),
+            Err(FetchError::HttpError(http::Error::Offline { .. }))
+        ));
+    }
+
+    #[test]
+    fn malformed_responses() {
+        let fetch = |body: &str| {
+            let server = StandIn::serve(body.as_bytes().to_vec(), 0);
+            let cache_dir = tempfile::tempdir().unwrap();
+            let http = &*backends()[0];
+            PhpNet::new(
+                http,
+                ReleaseCache::new(&cache_dir, None),
+                &server.url,
+                &server.url,
+            )
+            .fetch_all("8".parse().unwrap())
+        };
+
+        match fetch("<!DOCTYPE html>\n<title>Maintenance</title>") {
+            Err(FetchError::MalformedPayload { url, snippet, .. }) => {
+                assert!(url.contains("version=8"));
+                assert_eq!(snippet, "<!DOCTYPE html> <title>Maintenance</title>");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(matches!(
+            fetch(r#"{ "date": "31 Aug 2023", "source": [] }"#),
+            Err(FetchError::MissingVersion { .. })
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! Pre-release (alpha, beta, RC) source backed by the qa.php.net JSON API
+//!
+//! ```json
+//! {
+//!     "8.3": { "active": true, "release": { ... } },
+//!     "releases": {
+//!         "8.3": {
+//!             "version": "8.3.0RC6",
+//!             "date": "09 Nov 2023",
+//!             "files": {
+//!                 "gz": { "path": "https://downloads.php.net/~jakub/php-8.3.0RC6.tar.gz", "sha256": "..." },
+//!                 "xz": { "path": "https://downloads.php.net/~jakub/php-8.3.0RC6.tar.xz", "sha256": "..." }
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use super::{date_deserializer, FetchError, File, Hash, Release, ReleaseCache, Source};
+use crate::http::Transport;
+use crate::version::Version;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_URL: &str = "https://qa.php.net/api.php?type=qa-releases&format=json";
+
+#[derive(Deserialize, Debug)]
+struct Response {
+    #[serde(default)]
+    releases: BTreeMap<String, QaRelease>,
+}
+
+#[derive(Deserialize, Debug)]
+struct QaRelease {
+    version: Version,
+    #[serde(deserialize_with = "date_deserializer")]
+    date: NaiveDate,
+    files: BTreeMap<String, QaFile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct QaFile {
+    path: String,
+    #[serde(flatten)]
+    checksum: Option<Hash>,
+}
+
+impl From<QaRelease> for Release {
+    fn from(qa_release: QaRelease) -> Self {
+        let version = qa_release.version;
+        let source = qa_release
+            .files
+            .into_iter()
+            .map(|(extention, file)| {
+                Source::File(File {
+                    filename: file
+                        .path
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or(&file.path)
+                        .to_owned(),
+                    name: format!("PHP {} (tar.{})", version, extention),
+                    checksum: file.checksum,
+                    date: None,
+                    url: Some(file.path),
+                })
+            })
+            .collect();
+        Release {
+            announcement: None,
+            tags: None,
+            source,
+            windows_binary: None,
+            date: qa_release.date,
+            museum: None,
+            version: Some(version),
+        }
+    }
+}
+
+fn parse(json: &[u8], url: &str) -> Result<BTreeMap<Version, Release>, FetchError> {
+    let resp: Response =
+        serde_json::from_slice(json).map_err(|source| FetchError::malformed(url, json, source))?;
+    Ok(resp
+        .releases
+        .into_values()
+        .map(|qa_release| (qa_release.version, qa_release.into()))
+        .collect())
+}
+
+/// Fetches pre-releases currently under QA
+pub fn fetch_all(
+    http: &dyn Transport,
+    cache: &ReleaseCache,
+    url: &str,
+) -> Result<BTreeMap<Version, Release>, FetchError> {
+    cache.fetch(http, url, |json| parse(json, url))
+}
+
+/// Fetches the latest pre-release included by `version`.
+/// An earlier pre-release of a branch is looked up next to the one listed,
+/// in the directory of its release manager.
+pub fn fetch_latest(
+    http: &dyn Transport,
+    cache: &ReleaseCache,
+    version: Version,
+    url: &str,
+) -> Result<Release, FetchError> {
+    let mut releases = fetch_all(http, cache, url)?;
+    let included = releases
+        .keys()
+        .rev()
+        .find(|qa_version| version.includes(qa_version))
+        .copied();
+    if let Some(release) = included.and_then(|included| releases.remove(&included)) {
+        return Ok(release);
+    }
+    releases
+        .into_iter()
+        .find(|(listed, _)| listed.is_same_minor(version) && *listed > version)
+        .map(|(_, listed)| earlier(&listed, version))
+        .ok_or(FetchError::NotFoundPreRelease(version))
+}
+
+/// Pre-release `version` published before `listed`, without checksums as the API doesn't have them
+fn earlier(listed: &Release, version: Version) -> Release {
+    let archive_name = |filename: &str| {
+        let extension = filename.split(".tar.").nth(1).unwrap_or("gz");
+        format!("php-{}.tar.{}", version, extension)
+    };
+    let source = listed
+        .source
+        .iter()
+        .filter_map(|source| match source {
+            Source::File(file) => Some(Source::File(File {
+                filename: archive_name(&file.filename),
+                name: file
+                    .name
+                    .replace(&listed.version?.to_string(), &version.to_string()),
+                checksum: None,
+                date: None,
+                url: file.url.as_ref().map(|url| {
+                    let (dir, filename) = url.rsplit_once('/').unwrap_or(("", url));
+                    format!("{}/{}", dir, archive_name(filename))
+                }),
+            })),
+            _ => None,
+        })
+        .collect();
+    Release {
+        announcement: None,
+        tags: None,
+        source,
+        windows_binary: None,
+        date: listed.date,
+        museum: None,
+        version: Some(version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::DEFAULT_PREFERENCE;
+    use crate::http::tests::{backends, StandIn};
+    use crate::release::{SourceUrls, DEFAULT_MIRROR, DEFAULT_QA_MIRROR};
+    use chrono::Duration;
+
+    const JSON: &str = r#"
+        {
+            "8.3": { "active": true, "release": { "type": "RC", "number": 6 } },
+            "8.4": { "active": true, "release": { "type": "beta", "number": 2 } },
+            "releases": {
+                "8.3": {
+                    "version": "8.3.0RC6",
+                    "date": "09 Nov 2023",
+                    "files": {
+                        "gz": {
+                            "path": "https://downloads.php.net/~jakub/php-8.3.0RC6.tar.gz",
+                            "sha256": "4e4cf3f843a5111f6c55cd21de8f26834ea3cd4a5be77c88357cbcec4a2d671d"
+                        }
+                    }
+                },
+                "8.4": {
+                    "version": "8.4.0beta2",
+                    "date": "01 Aug 2024",
+                    "files": {
+                        "gz": {
+                            "path": "https://downloads.php.net/~saki/php-8.4.0beta2.tar.gz",
+                            "sha256": "8f8bc9cad6cd124edc111f7db0a109745e2f638770a101b3c22a2953f7a9b40e"
+                        }
+                    }
+                }
+            }
+        }
+    "#;
+
+    #[test]
+    fn deserialize() {
+        let json = JSON;
+        let releases = parse(json.as_bytes(), DEFAULT_URL).unwrap();
+        let beta2: Version = "8.4.0beta2".parse().unwrap();
+        assert_eq!(releases.keys().last(), Some(&beta2));
+
+        let SourceUrls { urls, checksum, .. } = releases[&beta2]
+            .source_urls(
+                &DEFAULT_PREFERENCE,
+                &[DEFAULT_MIRROR],
+                &[],
+                &[DEFAULT_QA_MIRROR],
+            )
+            .unwrap();
+        assert_eq!(
+            urls,
+            vec!["https://downloads.php.net/~saki/php-8.4.0beta2.tar.gz"]
+        );
+        // through a proxy of downloads.php.net
+        let SourceUrls { urls, .. } = releases[&beta2]
+            .source_urls(
+                &DEFAULT_PREFERENCE,
+                &[DEFAULT_MIRROR],
+                &[],
+                &["https://proxy.example.com/php/qa/"],
+            )
+            .unwrap();
+        assert_eq!(
+            urls,
+            vec!["https://proxy.example.com/php/qa/~saki/php-8.4.0beta2.tar.gz"]
+        );
+        assert!(matches!(checksum, Some(Hash::SHA256(_))));
+
+        assert!(matches!(
+            parse(b"<html>Service Unavailable</html>", DEFAULT_URL),
+            Err(FetchError::MalformedPayload { snippet, .. }) if snippet.starts_with("<html>")
+        ));
+    }
+
+    #[test]
+    fn earlier_pre_release() {
+        let server = StandIn::serve(JSON.as_bytes().to_vec(), 0);
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache = ReleaseCache::new(&cache_dir, Some(Duration::hours(1)));
+        let fetch = |version: &str| {
+            fetch_latest(
+                &*backends()[0],
+                &cache,
+                version.parse().unwrap(),
+                &server.url,
+            )
+        };
+
+        let beta1 = fetch("8.4.0beta1").unwrap();
+        assert_eq!(beta1.version, Some("8.4.0beta1".parse().unwrap()));
+        let SourceUrls { urls, checksum, .. } = beta1
+            .source_urls(&DEFAULT_PREFERENCE, &[], &[], &[DEFAULT_QA_MIRROR])
+            .unwrap();
+        assert_eq!(
+            urls,
+            vec!["https://downloads.php.net/~saki/php-8.4.0beta1.tar.gz"]
+        );
+        assert_eq!(checksum, None);
+
+        assert!(matches!(
+            fetch("8.4.0RC1"),
+            Err(FetchError::NotFoundPreRelease(_))
+        ));
+        assert!(matches!(
+            fetch("8.2.0RC1"),
+            Err(FetchError::NotFoundPreRelease(_))
+        ));
+    }
+}
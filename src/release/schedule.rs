@@ -0,0 +1,250 @@
+//! Support periods of each minor version branch, in the format of `https://endoflife.date/api/php.json`
+//!
+//! ```json
+//! [
+//!     { "cycle": "8.2", "releaseDate": "2022-12-08", "support": "2024-12-31", "eol": "2026-12-31" }
+//! ]
+//! ```
+//!
+//! `support` is the end of active support, and `eol` is the end of security support.
+//! Either may be `true` if it ended on an unknown date, or `false` if it hasn't been scheduled.
+
+use super::Support;
+use crate::http::{self, Transport};
+use crate::version::Version;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use sha2::Digest;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Taken from https://www.php.net/supported-versions.php, used unless another schedule is given
+const EMBEDDED: &str = include_str!("schedule.json");
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    HttpError(#[from] http::Error),
+
+    #[error("Can't read the support schedule from {path}: {source}")]
+    FailedRead {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Can't parse the support schedule from {location}: {source}")]
+    FailedParse {
+        location: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cycle {
+    cycle: String,
+    release_date: NaiveDate,
+    support: End,
+    eol: End,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum End {
+    Date(NaiveDate),
+    Ended(bool),
+}
+
+/// Support periods of a minor version branch such as `8.2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    pub release_date: NaiveDate,
+    /// `None` if it hasn't been scheduled yet
+    pub active_support_until: Option<NaiveDate>,
+    pub security_support_until: Option<NaiveDate>,
+}
+
+impl Branch {
+    pub fn support_on(&self, date: NaiveDate) -> Support {
+        let before = |until: Option<NaiveDate>| until.is_none_or(|until| date < until);
+        if before(self.active_support_until) {
+            Support::ActiveSupport
+        } else if before(self.security_support_until) {
+            Support::SecurityFixesOnly
+        } else {
+            Support::EndOfLife
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    branches: BTreeMap<(usize, usize), Branch>,
+}
+
+impl Schedule {
+    pub fn embedded() -> Self {
+        Self::parse("(embedded)", EMBEDDED.as_bytes()).expect("The embedded schedule is broken")
+    }
+    pub fn parse(location: &str, json: &[u8]) -> Result<Self, Error> {
+        let cycles: Vec<Cycle> =
+            serde_json::from_slice(json).map_err(|source| Error::FailedParse {
+                location: location.to_owned(),
+                source,
+            })?;
+        let branches = cycles
+            .into_iter()
+            .filter_map(|cycle| {
+                let (major, minor) = cycle.cycle.split_once('.')?;
+                let end = |end| match end {
+                    End::Date(date) => Some(date),
+                    // ended on an unknown date
+                    End::Ended(true) => Some(cycle.release_date),
+                    End::Ended(false) => None,
+                };
+                let branch = Branch {
+                    release_date: cycle.release_date,
+                    active_support_until: end(cycle.support),
+                    security_support_until: end(cycle.eol),
+                };
+                Some(((major.parse().ok()?, minor.parse().ok()?), branch))
+            })
+            .collect();
+        Ok(Self { branches })
+    }
+    /// Loads the schedule from a URL or a file. A fetched one is cached in `cache_dir` for `ttl`,
+    /// or forever if `ttl` is `None`, and the stale cache is used if fetching fails.
+    pub fn load(
+        http: &dyn Transport,
+        location: &str,
+        cache_dir: impl AsRef<Path>,
+        ttl: Option<Duration>,
+    ) -> Result<Self, Error> {
+        if !location.contains("://") {
+            let json = fs::read(location).map_err(|source| Error::FailedRead {
+                path: PathBuf::from(location),
+                source,
+            })?;
+            return Self::parse(location, &json);
+        }
+        let key = sha2::Sha256::digest(location.as_bytes());
+        let cache_path = cache_dir.as_ref().join(format!("schedule-{:x}.json", key));
+        let cached = fs::read(&cache_path).ok();
+        let is_fresh = fs::metadata(&cache_path)
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| {
+                let age = Utc::now() - chrono::DateTime::<Utc>::from(modified);
+                ttl.is_none_or(|ttl| age < ttl)
+            })
+            .unwrap_or(false);
+        match cached {
+            Some(json) if is_fresh => Self::parse(location, &json),
+            cached => match http.get(location) {
+                Ok(json) => {
+                    let schedule = Self::parse(location, &json)?;
+                    // failing to cache must not fail the command
+                    fs::create_dir_all(&cache_dir).ok();
+                    fs::write(&cache_path, &json).ok();
+                    Ok(schedule)
+                }
+                Err(e) => match cached {
+                    Some(json) => Self::parse(location, &json),
+                    None => Err(e.into()),
+                },
+            },
+        }
+    }
+    /// Support periods of the branch of `version`
+    pub fn branch(&self, version: Version) -> Option<&Branch> {
+        let minor = version.minor_version()?;
+        self.branches.get(&(version.major_version(), minor))
+    }
+    /// Support status of the branch of `version` as of today
+    pub fn support(&self, version: Version) -> Option<Support> {
+        let today = Utc::now().naive_local().date();
+        self.branch(version).map(|branch| branch.support_on(today))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn support_of_branches() {
+        let schedule = Schedule::parse(
+            "test",
+            br#"[
+                { "cycle": "8.1", "releaseDate": "2021-11-25", "support": "2023-11-25", "eol": "2025-12-31", "latest": "8.1.33" },
+                { "cycle": "5.2", "releaseDate": "2006-11-02", "support": true, "eol": true },
+                { "cycle": "9.0", "releaseDate": "2030-11-21", "support": false, "eol": false }
+            ]"#,
+        )
+        .unwrap();
+        let branch = schedule.branch("8.1.12".parse().unwrap()).unwrap();
+        assert_eq!(
+            branch.support_on(date("2023-11-24")),
+            Support::ActiveSupport
+        );
+        // extended beyond 3 years after the release
+        assert_eq!(
+            branch.support_on(date("2025-06-01")),
+            Support::SecurityFixesOnly
+        );
+        assert_eq!(branch.support_on(date("2025-12-31")), Support::EndOfLife);
+
+        assert_eq!(
+            schedule.support("5.2".parse().unwrap()),
+            Some(Support::EndOfLife)
+        );
+        assert_eq!(
+            schedule.support("9.0.0".parse().unwrap()),
+            Some(Support::ActiveSupport)
+        );
+        assert_eq!(schedule.support("8".parse().unwrap()), None);
+        assert_eq!(schedule.support("7.4".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn embedded_schedule() {
+        let schedule = Schedule::embedded();
+        for version in [
+            "3.0", "4.4", "5.6", "7.4", "8.0", "8.1", "8.2", "8.3", "8.4",
+        ] {
+            assert!(schedule.branch(version.parse().unwrap()).is_some());
+        }
+    }
+
+    #[test]
+    fn load_with_cache() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let file = cache_dir.path().join("php.json");
+        fs::write(&file, EMBEDDED).unwrap();
+        assert!(Schedule::load(&http::Offline, file.to_str().unwrap(), &cache_dir, None).is_ok());
+
+        // offline without a cache
+        let url = "https://example.com/php.json";
+        assert!(matches!(
+            Schedule::load(&http::Offline, url, &cache_dir, None),
+            Err(Error::HttpError(_))
+        ));
+        let key = sha2::Sha256::digest(url.as_bytes());
+        fs::write(
+            cache_dir.path().join(format!("schedule-{:x}.json", key)),
+            EMBEDDED,
+        )
+        .unwrap();
+        // a stale cache is used if the fetch fails
+        let schedule =
+            Schedule::load(&http::Offline, url, &cache_dir, Some(Duration::zero())).unwrap();
+        assert!(schedule.branch("8.2".parse().unwrap()).is_some());
+    }
+}
@@ -47,7 +47,7 @@ impl Shell {
                 .command
                 .trim_start_matches('-')
                 .split('/')
-                .last()
+                .next_back()
                 .unwrap();
             if let Ok(shell) = Self::from_str(binary) {
                 return Ok(shell);
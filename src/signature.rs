@@ -0,0 +1,196 @@
+//! OpenPGP verification of source archives by `gpgv` against the keys of the PHP release managers
+
+use derive_more::Display;
+use serde_with::DeserializeFromStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use strum::EnumVariantNames;
+use thiserror::Error;
+
+pub const DEFAULT_KEYRING_FILE_NAME: &str = "php-keyring.gpg";
+pub const KEYRING_URL: &str = "https://www.php.net/distributions/php-keyring.gpg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumVariantNames, DeserializeFromStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum Policy {
+    /// Fail unless the archive has a good signature
+    Required,
+    /// Verify the signature if both it and the keyring are available
+    #[default]
+    Optional,
+    /// Don't verify signatures
+    Off,
+}
+
+#[derive(Error, Debug)]
+pub enum ParsePolicyError {
+    #[error("Unknown signature policy: {0}")]
+    UnknownPolicy(String),
+}
+
+impl FromStr for Policy {
+    type Err = ParsePolicyError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "required" => Ok(Policy::Required),
+            "optional" => Ok(Policy::Optional),
+            "off" => Ok(Policy::Off),
+            _ => Err(ParsePolicyError::UnknownPolicy(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Keyring {0} doesn't exist; Download it from {KEYRING_URL}")]
+    NoKeyring(PathBuf),
+
+    #[error("Signature of {0} isn't available")]
+    NoSignature(PathBuf),
+
+    #[error("Bad signature of {path}\n{output}")]
+    BadSignature { path: PathBuf, output: String },
+
+    #[error("Can't execute gpgv: {0}")]
+    FailedExecute(#[source] std::io::Error),
+}
+
+/// Key that made a good signature
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+#[display(fmt = "{} ({})", user_id, key_id)]
+pub struct Signer {
+    pub key_id: String,
+    pub user_id: String,
+}
+
+/// Path of the detached signature of `filepath`
+pub fn signature_path(filepath: impl AsRef<Path>) -> PathBuf {
+    let mut path = filepath.as_ref().as_os_str().to_owned();
+    path.push(".asc");
+    path.into()
+}
+
+/// Verifies `filepath` by its detached signature `<filepath>.asc`
+pub fn verify(keyring: impl AsRef<Path>, filepath: impl AsRef<Path>) -> Result<Signer, Error> {
+    let filepath = filepath.as_ref();
+    // gpgv looks up a relative keyring in its home directory
+    let keyring = keyring
+        .as_ref()
+        .canonicalize()
+        .map_err(|_| Error::NoKeyring(keyring.as_ref().to_owned()))?;
+    let signature = signature_path(filepath);
+    if !signature.is_file() {
+        return Err(Error::NoSignature(filepath.to_owned()));
+    }
+    let output = Command::new("gpgv")
+        .arg("--status-fd")
+        .arg("1")
+        .arg("--keyring")
+        .arg(keyring)
+        .arg(&signature)
+        .arg(filepath)
+        .output()
+        .map_err(Error::FailedExecute)?;
+    let status = String::from_utf8_lossy(&output.stdout);
+    match good_signer(&status) {
+        Some(signer) if output.status.success() => Ok(signer),
+        _ => Err(Error::BadSignature {
+            path: filepath.to_owned(),
+            output: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_owned(),
+        }),
+    }
+}
+
+/// Parses `[GNUPG:] GOODSIG <key id> <user id>` of the `--status-fd` output
+fn good_signer(status: &str) -> Option<Signer> {
+    status.lines().find_map(|line| {
+        let (key_id, user_id) = line.strip_prefix("[GNUPG:] GOODSIG ")?.split_once(' ')?;
+        Some(Signer {
+            key_id: key_id.to_owned(),
+            user_id: user_id.to_owned(),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parse_status() {
+        let status = "\
+[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 39B641343D8C104B2B146DC3F9C39DC0B9698544 0
+[GNUPG:] SIG_ID 8rJ4MQ0nAM4gJ6/yMwHH0EK2hEY 2023-08-31 1693478400
+[GNUPG:] GOODSIG F9C39DC0B9698544 Ben Ramsey <ramsey@php.net>
+[GNUPG:] VALIDSIG 39B641343D8C104B2B146DC3F9C39DC0B9698544 2023-08-31 1693478400 0 4 0 1 10 00 39B641343D8C104B2B146DC3F9C39DC0B9698544
+";
+        assert_eq!(
+            good_signer(status),
+            Some(Signer {
+                key_id: "F9C39DC0B9698544".to_owned(),
+                user_id: "Ben Ramsey <ramsey@php.net>".to_owned(),
+            })
+        );
+        assert_eq!(good_signer("[GNUPG:] BADSIG F9C39DC0B9698544 Ben"), None);
+    }
+
+    #[test]
+    fn verify_by_gpgv() {
+        if which::which("gpg").is_err() || which::which("gpgv").is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let gpg = |args: &[&str]| {
+            let status = Command::new("gpg")
+                .env("GNUPGHOME", dir.path())
+                .args([
+                    "--batch",
+                    "--quiet",
+                    "--pinentry-mode",
+                    "loopback",
+                    "--passphrase",
+                    "",
+                ])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "gpg {:?} failed", args);
+        };
+        let archive = dir.path().join("php-8.2.10.tar.xz");
+        let keyring = dir.path().join("keyring.gpg");
+        fs::write(&archive, b"archive").unwrap();
+        gpg(&[
+            "--quick-gen-key",
+            "Release Manager <rm@example.com>",
+            "ed25519",
+            "sign",
+            "never",
+        ]);
+        gpg(&["--output", keyring.to_str().unwrap(), "--export"]);
+        gpg(&["--armor", "--detach-sign", archive.to_str().unwrap()]);
+
+        let signer = verify(&keyring, &archive).unwrap();
+        assert_eq!(signer.user_id, "Release Manager <rm@example.com>");
+
+        fs::write(&archive, b"tampered").unwrap();
+        assert!(matches!(
+            verify(&keyring, &archive),
+            Err(Error::BadSignature { .. })
+        ));
+        assert!(matches!(
+            verify(dir.path().join("none.gpg"), &archive),
+            Err(Error::NoKeyring(_))
+        ));
+        fs::remove_file(signature_path(&archive)).unwrap();
+        assert!(matches!(
+            verify(&keyring, &archive),
+            Err(Error::NoSignature(_))
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::version::Version;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A bundled extension toggled by `+name` or `-name` on install
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SerializeDisplay, DeserializeFromStr)]
+pub struct Variant {
+    name: String,
+    enabled: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Invalid variant format: \"{0}\"; Use `+name` to enable or `-name` to disable")]
+    InvalidFormat(String),
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("Unknown variant '{0}'")]
+    UnknownVariant(String),
+
+    #[error("Variant '{name}' is not available for PHP {version}")]
+    UnsupportedVariant { name: String, version: Version },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flag {
+    Enable(&'static str),
+    With(&'static str),
+}
+
+impl Flag {
+    fn to_option(self, enabled: bool) -> String {
+        match (self, enabled) {
+            (Flag::Enable(name), true) => format!("--enable-{}", name),
+            (Flag::Enable(name), false) => format!("--disable-{}", name),
+            (Flag::With(name), true) => format!("--with-{}", name),
+            (Flag::With(name), false) => format!("--without-{}", name),
+        }
+    }
+}
+
+/// Returns the configure flags of a variant for the given PHP version
+fn flags(name: &str, version: Version) -> Result<Vec<Flag>, Error> {
+    use Flag::*;
+
+    let since = |major: usize, minor: usize| {
+        (
+            version.major_version(),
+            version.minor_version().unwrap_or(0),
+        ) >= (major, minor)
+    };
+    let unsupported = || Error::UnsupportedVariant {
+        name: name.to_owned(),
+        version,
+    };
+
+    let flags = match name {
+        "bcmath" => vec![Enable("bcmath")],
+        "bz2" => vec![With("bz2")],
+        "calendar" => vec![Enable("calendar")],
+        "cgi" => vec![Enable("cgi")],
+        "cli" => vec![Enable("cli")],
+        "curl" => vec![With("curl")],
+        "dba" => vec![Enable("dba")],
+        "exif" => vec![Enable("exif")],
+        "ffi" if since(7, 4) => vec![With("ffi")],
+        "ffi" => return Err(unsupported()),
+        "fpm" => vec![Enable("fpm")],
+        "ftp" => vec![Enable("ftp")],
+        "gd" if since(7, 4) => vec![Enable("gd")],
+        "gd" => vec![With("gd")],
+        "gettext" => vec![With("gettext")],
+        "gmp" => vec![With("gmp")],
+        "iconv" => vec![With("iconv")],
+        "intl" => vec![Enable("intl")],
+        "mbstring" => vec![Enable("mbstring")],
+        "mysql" => vec![With("mysqli"), With("pdo-mysql")],
+        "opcache" => vec![Enable("opcache")],
+        "openssl" => vec![With("openssl")],
+        "password-argon2" if since(7, 2) => vec![With("password-argon2")],
+        "password-argon2" => return Err(unsupported()),
+        "pcntl" => vec![Enable("pcntl")],
+        "pdo" => vec![Enable("pdo")],
+        "pgsql" => vec![With("pgsql"), With("pdo-pgsql")],
+        "phpdbg" => vec![Enable("phpdbg")],
+        "readline" => vec![With("readline")],
+        "soap" => vec![Enable("soap")],
+        "sockets" => vec![Enable("sockets")],
+        "sodium" if since(7, 2) => vec![With("sodium")],
+        "sodium" => return Err(unsupported()),
+        "sqlite" => vec![With("sqlite3"), With("pdo-sqlite")],
+        "xml" => vec![Enable("xml")],
+        "xsl" => vec![With("xsl")],
+        "zip" if since(7, 4) => vec![With("zip")],
+        "zip" => vec![Enable("zip")],
+        "zlib" => vec![With("zlib")],
+        _ => return Err(Error::UnknownVariant(name.to_owned())),
+    };
+    Ok(flags)
+}
+
+impl Variant {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn configure_opts(&self, version: Version) -> Result<Vec<String>, Error> {
+        Ok(flags(&self.name, version)?
+            .into_iter()
+            .map(|flag| flag.to_option(self.enabled))
+            .collect())
+    }
+}
+
+/// Merges configure options of all variants; the last one wins if the same name is given twice
+pub fn configure_opts(variants: &[Variant], version: Version) -> Result<Vec<String>, Error> {
+    let mut opts = Vec::new();
+    for (i, variant) in variants.iter().enumerate() {
+        if variants[i + 1..].iter().any(|v| v.name == variant.name) {
+            continue;
+        }
+        opts.extend(variant.configure_opts(version)?);
+    }
+    Ok(opts)
+}
+
+impl FromStr for Variant {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (enabled, name) = match s.split_at(s.len().min(1)) {
+            ("+", name) => (true, name),
+            ("-", name) => (false, name),
+            _ => return Err(ParseError::InvalidFormat(s.to_owned())),
+        };
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(ParseError::InvalidFormat(s.to_owned()));
+        }
+        Ok(Self {
+            name: name.to_lowercase().replace('_', "-"),
+            enabled,
+        })
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        format!("{}{}", if self.enabled { "+" } else { "-" }, self.name).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let intl: Variant = "+intl".parse().unwrap();
+        assert_eq!(intl.name(), "intl");
+        assert!(intl.is_enabled());
+
+        let cgi: Variant = "-cgi".parse().unwrap();
+        assert_eq!(cgi.name(), "cgi");
+        assert!(!cgi.is_enabled());
+
+        assert!("intl".parse::<Variant>().is_err());
+        assert!("+".parse::<Variant>().is_err());
+        assert!("+in tl".parse::<Variant>().is_err());
+    }
+
+    #[test]
+    fn depends_on_version() {
+        let gd: Variant = "+gd".parse().unwrap();
+        assert_eq!(
+            gd.configure_opts("7.3.33".parse().unwrap()).unwrap(),
+            vec!["--with-gd"]
+        );
+        assert_eq!(
+            gd.configure_opts("8.2.0".parse().unwrap()).unwrap(),
+            vec!["--enable-gd"]
+        );
+
+        let sodium: Variant = "+sodium".parse().unwrap();
+        assert!(matches!(
+            sodium.configure_opts("7.1.0".parse().unwrap()),
+            Err(Error::UnsupportedVariant { .. })
+        ));
+    }
+
+    #[test]
+    fn merge() {
+        let variants = ["+intl", "+pgsql", "-cgi", "+cgi"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect::<Vec<Variant>>();
+        assert_eq!(
+            configure_opts(&variants, "8.2.10".parse().unwrap()).unwrap(),
+            vec![
+                "--enable-intl",
+                "--with-pgsql",
+                "--with-pdo-pgsql",
+                "--enable-cgi"
+            ]
+        );
+
+        let unknown = vec!["+unknown".parse().unwrap()];
+        assert_eq!(
+            configure_opts(&unknown, "8.2.10".parse().unwrap()),
+            Err(Error::UnknownVariant("unknown".to_owned()))
+        );
+    }
+}
@@ -20,6 +20,26 @@ pub fn installed(config: &Config) -> impl Iterator<Item = Version> {
         .sorted()
 }
 
+/// Names of the installs labeled instead of versioned, e.g. `8.4-dev`
+pub fn labeled(config: &Config) -> impl Iterator<Item = String> {
+    let versions_dir = config.versions_dir();
+    std::fs::read_dir(&versions_dir)
+        .unwrap()
+        .flatten()
+        .flat_map(|path| path.path().file_name().map(ToOwned::to_owned))
+        .flat_map(|dir_os_str| dir_os_str.into_string())
+        .filter(|dir_str| matches!(dir_str.parse(), Ok(Local::Labeled(_))))
+        .filter(|label| {
+            versions_dir
+                .join(label)
+                // TODO: windows
+                .join("bin")
+                .join("php")
+                .is_file()
+        })
+        .sorted()
+}
+
 pub fn installed_by<'a>(
     version: &'a Version,
     config: &Config,
@@ -21,6 +21,15 @@ pub struct File {
     is_recursive: bool,
 }
 
+impl Default for File {
+    fn default() -> Self {
+        Self {
+            filename: PathBuf::from(DEFAULT_VERSION_FILE_NAME),
+            is_recursive: false,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Can't parse string written in {filepath}: {source}")]
@@ -40,7 +49,7 @@ pub struct FileInfo {
 impl FileInfo {
     fn to_relative_path(&self, base_dir: impl AsRef<Path>) -> Self {
         Self {
-            version: self.version,
+            version: self.version.clone(),
             filepath: diff_paths(&self.filepath, base_dir).unwrap(),
         }
     }
@@ -2,13 +2,21 @@ use super::semantic;
 use super::system;
 use crate::config::Config;
 use crate::decorized::{color::Color, Decorized};
+use crate::release::Support;
 use colored::Colorize;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use std::fmt::Display;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+/// Names of installs built from a git ref, e.g. `8.4-dev` or `master-1a2b3c4`
+static LABEL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[0-9A-Za-z][0-9A-Za-z._+]*(-[0-9A-Za-z._+]+)+$").unwrap());
+
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
 pub enum Local {
     Installed(semantic::Version),
+    Labeled(String),
     System,
 }
 
@@ -16,6 +24,7 @@ impl Display for Local {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Local::Installed(version) => format!("{}", version).fmt(f),
+            Local::Labeled(label) => label.fmt(f),
             Local::System => "system".fmt(f),
         }
     }
@@ -26,6 +35,8 @@ impl FromStr for Local {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s == "system" {
             Ok(Self::System)
+        } else if LABEL_REGEX.is_match(s) {
+            Ok(Self::Labeled(s.to_owned()))
         } else {
             s.parse::<semantic::Version>().map(Self::Installed)
         }
@@ -36,7 +47,7 @@ impl Local {
     pub fn as_version(&self) -> Option<semantic::Version> {
         match self {
             Local::Installed(version) => Some(*version),
-            Local::System => None,
+            Local::Labeled(_) | Local::System => None,
         }
     }
     pub fn current(config: &Config) -> Option<Self> {
@@ -56,30 +67,67 @@ impl Local {
                             .unwrap()
                             .parse()
                             .ok()
-                            .map(Local::Installed)
                     })
             })
     }
+    /// The installed version or label this specifies, e.g. the latest installed `8.1.x` for `8.1`
+    pub fn installed(&self, config: &Config) -> Option<Self> {
+        match self {
+            Local::Installed(version) => {
+                super::latest_installed_by(version, config).map(Local::Installed)
+            }
+            Local::Labeled(label) => super::labeled(config)
+                .find(|labeled| labeled == label)
+                .map(Local::Labeled),
+            Local::System => None,
+        }
+    }
     pub fn local(config: &Config) -> impl Iterator<Item = Self> {
         let installed = super::installed(config).map(Local::Installed);
+        let labeled = super::labeled(config).map(Local::Labeled);
         let system = system::path().map(|_| Local::System);
-        installed.chain(system.into_iter())
+        installed.chain(labeled).chain(system)
     }
-    pub fn to_string_by(&self, installed: bool, used: bool) -> String {
-        let output = format!(
-            "{:<2}{:<6} {}",
-            installed.then_some("*").unwrap_or_default(),
-            self,
-            used.then_some("<-").unwrap_or_default(),
-        );
+    /// One line of `list` and `list-remote`, with the support status of the branch if known
+    pub fn to_string_by(&self, installed: bool, used: bool, support: Option<Support>) -> String {
+        let version = format!("{:<2}{:<6}", if installed { "*" } else { "" }, self);
+        let support_column = Support::column(support);
+        let current = if used { "<-" } else { "" };
 
         if used {
+            let output = format!("{} {} {}", version, &*support_column, current);
             format!("{}", output.color(colored::Color::Green).bold())
         } else {
             format!(
-                "{}",
-                output.color(<semantic::Version as Decorized>::Color::color())
+                "{} {} {}",
+                version.color(<semantic::Version as Decorized>::Color::color()),
+                support_column,
+                current
             )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_label() {
+        assert_eq!(
+            "8.4-dev".parse::<Local>().unwrap(),
+            Local::Labeled("8.4-dev".to_owned())
+        );
+        assert_eq!(
+            "master-1a2b3c4".parse::<Local>().unwrap(),
+            Local::Labeled("master-1a2b3c4".to_owned())
+        );
+        assert_eq!(
+            "8.4".parse::<Local>().unwrap(),
+            Local::Installed("8.4".parse().unwrap())
+        );
+        assert_eq!("system".parse::<Local>().unwrap(), Local::System);
+        assert!("master".parse::<Local>().is_err());
+        assert!("../8.4-dev".parse::<Local>().is_err());
+    }
+}
@@ -1,7 +1,8 @@
 use derive_more::Display;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde::{de, Deserialize, Deserializer, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
 use std::fmt;
 use std::str::FromStr;
 use thiserror::Error;
@@ -31,27 +32,28 @@ static VERSION_REGEX: Lazy<Regex> = Lazy::new(|| {
 
 pub type Version = Major;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Major {
     version: usize,
     minor: Option<Minor>,
 }
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Minor {
     version: usize,
     patch: Option<Patch>,
 }
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 struct Patch {
     version: usize,
     pre: Option<Pre>,
 }
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+// field order matters for the derived `Ord`: `alpha2` < `beta1`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Pre {
-    version: usize,
     pre_type: PreType,
+    version: usize,
 }
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
 pub enum PreType {
     #[display(fmt = "alpha")]
     Alpha,
@@ -61,6 +63,25 @@ pub enum PreType {
     Rc,
 }
 
+// a pre-release precedes its final release: `8.1.0RC1` < `8.1.0`
+impl Ord for Patch {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.version
+            .cmp(&other.version)
+            .then_with(|| match (self.pre, other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(pre), Some(other_pre)) => pre.cmp(&other_pre),
+            })
+    }
+}
+impl PartialOrd for Patch {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Version {
     pub fn from_numbers(major: usize, minor: Option<usize>, patch: Option<usize>) -> Self {
         Self {
@@ -82,11 +103,11 @@ impl Version {
     /// - `3.1` includes `3.1`, `3.1.x`
     pub fn includes(&self, other: &Self) -> bool {
         self.major_version() == other.major_version()
-            && self.minor.map_or(true, |Minor { version, patch }| {
+            && self.minor.is_none_or(|Minor { version, patch }| {
                 Some(version) == other.minor_version()
-                    && (patch.map_or(true, |Patch { version, pre }| {
+                    && (patch.is_none_or(|Patch { version, pre }| {
                         Some(version) == other.patch_version()
-                            && pre.map_or(true, |pre| Some(pre) == other.pre())
+                            && pre.is_none_or(|pre| Some(pre) == other.pre())
                     }))
             })
     }
@@ -114,6 +135,9 @@ impl Version {
     pub fn pre_version(self) -> Option<usize> {
         self.pre().map(|pre| pre.version)
     }
+    pub fn is_pre_release(self) -> bool {
+        self.pre().is_some()
+    }
 
     pub fn is_same_major(self, other: Self) -> bool {
         self.major_version() == other.major_version()
@@ -154,8 +178,8 @@ impl FromStr for Version {
                 patch: cap.get(3).map(to_num).map(|version| Patch {
                     version,
                     pre: cap.get(5).map(to_num).map(|version| Pre {
-                        version,
                         pre_type: PreType::from_str(&cap[4]).unwrap(),
+                        version,
                     }),
                 }),
             }),
@@ -208,6 +232,15 @@ impl<'de> Deserialize<'de> for Version {
     }
 }
 
+impl Serialize for Version {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
 impl FromStr for PreType {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -226,6 +259,7 @@ mod tests {
     use std::collections::HashMap;
 
     #[test]
+    #[allow(clippy::redundant_pattern_matching)]
     fn parsed_from_str() {
         let version3_1_4: Result<Version, _> = "3.1.4".parse();
         assert!(matches!(version3_1_4, Ok(_)));
@@ -251,6 +285,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_to_json() {
+        let version: Version = "8.3.0RC1".parse().unwrap();
+        assert_eq!(serde_json::to_string(&version).unwrap(), r#""8.3.0RC1""#);
+        let parsed: Version = serde_json::from_str(r#""8.3.0RC1""#).unwrap();
+        assert_eq!(parsed, version);
+    }
+
+    #[test]
+    fn pre_release_ordering() {
+        let versions = [
+            "8.0.30",
+            "8.1.0alpha1",
+            "8.1.0alpha2",
+            "8.1.0beta1",
+            "8.1.0RC1",
+            "8.1.0RC2",
+            "8.1.0",
+            "8.1.1",
+        ]
+        .iter()
+        .map(|s| s.parse::<Version>().unwrap())
+        .collect::<Vec<_>>();
+        let mut sorted = versions.clone();
+        sorted.reverse();
+        sorted.sort();
+        assert_eq!(sorted, versions);
+        assert_eq!(versions.iter().max(), versions.last());
+    }
+
     #[test]
     fn includes_test() {
         let version3_1_4 = Version::from_numbers(3, Some(1), Some(4));